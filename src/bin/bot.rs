@@ -0,0 +1,73 @@
+use roguie::{new_seed, play_games, BotConfig};
+use std::env;
+use std::process;
+
+const USAGE: &str = "Usage: bot [--games N] [--seed S] [--heal-below F] [--max-turns N] \
+                     [--max-turns-per-depth N] [--json]
+
+Plays whole games without a window and reports how they went.
+
+  --games N              games to play (default 100)
+  --seed S               seed of the first game, the rest follow on (default random)
+  --heal-below F         drink a healing potion below this fraction of max hp (default 0.5)
+  --max-turns N          give up on a game after this many turns (default 10000)
+  --max-turns-per-depth N
+                         head for the stairs after this many turns on a level (default 1000)
+  --json                 print each game's report as a line of JSON instead of a summary";
+
+/// Parses the value following a flag, or quits with the usage
+fn value<T: std::str::FromStr>(flag: &str, arg: Option<String>) -> T {
+    match arg.and_then(|a| a.parse().ok()) {
+        Some(v) => v,
+        None => {
+            eprintln!("{} needs a valid value\n\n{}", flag, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut config = BotConfig::default();
+    let mut games: usize = 100;
+    let mut seed = new_seed();
+    let mut json = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = value(&arg, args.next()),
+            "--seed" => seed = value(&arg, args.next()),
+            "--heal-below" => config.heal_below = value(&arg, args.next()),
+            "--max-turns" => config.max_turns = value(&arg, args.next()),
+            "--max-turns-per-depth" => config.max_turns_per_depth = value(&arg, args.next()),
+            "--json" => json = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("Unknown argument {}\n\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    eprintln!("Playing {} games from seed {}", games, seed);
+    let report = play_games(seed, games, &config, |played, game| {
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string(game).expect("Unable to write report")
+            );
+        }
+        if played % 100 == 0 {
+            eprintln!("{} games played", played);
+        }
+    });
+
+    if !json {
+        for line in report.summary() {
+            println!("{}", line);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use rltk::rex::{XpCell, XpFile};
+use rltk::XpColor;
+use roguie::map_builders::{self, MapBuilder, BUILDER_NAMES};
+use roguie::{new_seed, tile_glyph, Map, Position, TileType};
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process;
+
+const USAGE: &str = "Usage: mapgen [--builder NAME] [--depth N] [--seed S] [--png FILE] \
+                     [--xp FILE] [--snapshots DIR] [--stats]
+
+Builds a level without the game and prints it as ASCII.
+
+  --builder NAME         bsp_dungeon, bsp_interior, simple, cellular or random (default random)
+  --depth N              depth to build the level for (default 1)
+  --seed S               game seed; the level is built as it would be in that game (default random)
+  --png FILE             also write the map as a PNG image
+  --xp FILE              also write the map as a REXPaint .xp file
+  --snapshots DIR        write every step of the build to DIR, as text and PNG
+  --stats                report floor coverage, rooms and the walk from start to stairs";
+
+/// How many pixels across each tile is in PNG exports
+const PNG_TILE_SIZE: usize = 4;
+
+/// Parses the value following a flag, or quits with the usage
+fn value<T: std::str::FromStr>(flag: &str, arg: Option<String>) -> T {
+    match arg.and_then(|a| a.parse().ok()) {
+        Some(v) => v,
+        None => {
+            eprintln!("{} needs a valid value\n\n{}", flag, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+/// Quits with the error if writing `path` went wrong
+fn check<T, E: std::fmt::Display>(result: Result<T, E>, path: &str) -> T {
+    match result {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Unable to write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let mut builder_name = "random".to_string();
+    let mut depth = 1;
+    let mut seed = new_seed();
+    let mut png: Option<String> = None;
+    let mut xp: Option<String> = None;
+    let mut snapshots: Option<String> = None;
+    let mut stats = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--builder" => builder_name = value(&arg, args.next()),
+            "--depth" => depth = value(&arg, args.next()),
+            "--seed" => seed = value(&arg, args.next()),
+            "--png" => png = Some(value(&arg, args.next())),
+            "--xp" => xp = Some(value(&arg, args.next())),
+            "--snapshots" => snapshots = Some(value(&arg, args.next())),
+            "--stats" => stats = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("Unknown argument {}\n\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    let level_seed = map_builders::level_seed(seed, depth);
+    let builder = if builder_name == "random" {
+        Some(map_builders::random_builder(depth, level_seed))
+    } else {
+        map_builders::named_builder(&builder_name, depth, level_seed)
+    };
+    let mut builder = match builder {
+        Some(builder) => builder,
+        None => {
+            eprintln!(
+                "Unknown builder {}, expected one of {} or random",
+                builder_name,
+                BUILDER_NAMES.join(", ")
+            );
+            process::exit(2);
+        }
+    };
+
+    if snapshots.is_some() {
+        builder.record_snapshot_history();
+    }
+    builder.build_map();
+    let map = builder.get_map();
+    let start = builder.get_starting_position();
+
+    eprintln!("Depth {} of seed {}", depth, seed);
+    for row in ascii_rows(&map, Some(&start)) {
+        println!("{}", row);
+    }
+
+    if let Some(path) = png {
+        check(write_png(&map, Some(&start), &path), &path);
+    }
+    if let Some(path) = xp {
+        check(write_xp(&map, Some(&start), &path), &path);
+    }
+    if let Some(dir) = snapshots {
+        check(fs::create_dir_all(&dir), &dir);
+        let history = builder.get_snapshot_history();
+        for (i, frame) in history.iter().enumerate() {
+            let text = format!("{}/frame-{:03}.txt", dir, i);
+            let mut rows = vec![frame.description.clone()];
+            rows.extend(ascii_rows(&frame.map, None));
+            check(fs::write(&text, rows.join("\n")), &text);
+            let image = format!("{}/frame-{:03}.png", dir, i);
+            check(write_png(&frame.map, None, &image), &image);
+        }
+        eprintln!("Wrote {} snapshots to {}", history.len(), dir);
+    }
+
+    if stats {
+        for line in map_stats(&map, &start, &*builder) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// The map as text, with `@` marking the start if there is one
+fn ascii_rows(map: &Map, start: Option<&Position>) -> Vec<String> {
+    let mut rows = Vec::new();
+    for y in 0..map.height {
+        let mut row = String::new();
+        for x in 0..map.width {
+            let c = match start {
+                Some(start) if start.x == x && start.y == y => '@',
+                _ => match map.tiles[map.xy_idx(x, y)] {
+                    TileType::Wall => '#',
+                    TileType::Floor => '.',
+                    TileType::DownStairs => '>',
+                },
+            };
+            row.push(c);
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+/// The colour a tile is drawn in, in exports
+fn tile_color(tile: TileType) -> (u8, u8, u8) {
+    match tile {
+        TileType::Wall => (0, 255, 0),
+        TileType::Floor => (0, 127, 127),
+        TileType::DownStairs => (0, 255, 255),
+    }
+}
+
+const START_COLOR: (u8, u8, u8) = (255, 255, 0);
+
+fn write_png(map: &Map, start: Option<&Position>, path: &str) -> Result<(), png::EncodingError> {
+    let width = map.width as usize * PNG_TILE_SIZE;
+    let height = map.height as usize * PNG_TILE_SIZE;
+    let mut data = vec![0u8; width * height * 3];
+    for y in 0..height {
+        for x in 0..width {
+            let (tx, ty) = ((x / PNG_TILE_SIZE) as i32, (y / PNG_TILE_SIZE) as i32);
+            let (r, g, b) = match start {
+                Some(start) if start.x == tx && start.y == ty => START_COLOR,
+                _ => tile_color(map.tiles[map.xy_idx(tx, ty)]),
+            };
+            let pixel = (y * width + x) * 3;
+            data[pixel] = r;
+            data[pixel + 1] = g;
+            data[pixel + 2] = b;
+        }
+    }
+
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)
+}
+
+fn write_xp(map: &Map, start: Option<&Position>, path: &str) -> std::io::Result<()> {
+    let mut xp = XpFile::new(map.width as usize, map.height as usize);
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let idx = map.xy_idx(x, y);
+            let (ch, (r, g, b)) = match start {
+                Some(start) if start.x == x && start.y == y => (rltk::to_cp437('@'), START_COLOR),
+                _ => (tile_glyph(idx, map).0, tile_color(map.tiles[idx])),
+            };
+            if let Some(cell) = xp.layers[0].get_mut(x as usize, y as usize) {
+                *cell = XpCell {
+                    ch: ch as u32,
+                    fg: XpColor::new(r, g, b),
+                    bg: XpColor::BLACK,
+                };
+            }
+        }
+    }
+    let mut file = File::create(path)?;
+    xp.write(&mut file)
+}
+
+/// Floor coverage, room count and the length of the walk from the start to
+/// the stairs
+fn map_stats(map: &Map, start: &Position, builder: &dyn MapBuilder) -> Vec<String> {
+    let floor = map
+        .tiles
+        .iter()
+        .filter(|tile| **tile != TileType::Wall)
+        .count();
+    let mut lines = vec![
+        format!("Size: {}x{}", map.width, map.height),
+        format!(
+            "Floor: {:.1}%",
+            floor as f32 * 100.0 / map.tiles.len() as f32
+        ),
+        format!("Rooms: {}", builder.get_rooms().len()),
+    ];
+
+    let mut walkable = map.clone();
+    walkable.populate_blocked();
+    let start_idx = walkable.xy_idx(start.x, start.y);
+    let path = walkable
+        .tiles
+        .iter()
+        .position(|tile| *tile == TileType::DownStairs)
+        .map(|stairs| rltk::a_star_search(start_idx, stairs, &walkable));
+    lines.push(match path {
+        Some(path) if path.success => format!("Start to stairs: {} steps", path.steps.len() - 1),
+        Some(_) => "Start to stairs: unreachable".to_string(),
+        None => "Start to stairs: no stairs".to_string(),
+    });
+    lines
+}
@@ -0,0 +1,633 @@
+use super::{
+    can_carry, cull_dead_particles, delete_the_dead, register_storages, stack_weight,
+    try_move_player, AreaOfEffect, CombatStats, Cursed, DefenseBonus, Encumbrance,
+    EncumbranceLevel, EntryTrigger, EquipmentSlot, Equippable, Equipped, Headless, Hidden,
+    HungerClock, HungerState, InBackpack, InflictsDamage, Item, Map, MeleePowerBonus, MonsterAI,
+    Position, ProvidesFood, ProvidesHealing, Ranged, RunState, RunStats, SerializeMe, Settings,
+    Stackable, State, TileType, TwoHanded, Vendor, WantsToPickupItem, WantsToUseItem, Weight,
+};
+use rltk::{DistanceAlg, Point};
+use serde::Serialize;
+use specs::prelude::*;
+use specs::saveload::SimpleMarkerAllocator;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// How the bot plays
+#[derive(Clone)]
+pub struct BotConfig {
+    /// Drinks a healing potion when below this fraction of its max hp
+    pub heal_below: f32,
+    /// Gives up on a game after this many turns
+    pub max_turns: i32,
+    /// Heads for the stairs after this many turns on a level, even if there's
+    /// more to explore
+    pub max_turns_per_depth: i32,
+}
+
+impl Default for BotConfig {
+    fn default() -> BotConfig {
+        BotConfig {
+            heal_below: 0.5,
+            max_turns: 10000,
+            max_turns_per_depth: 1000,
+        }
+    }
+}
+
+/// How a game played by the bot went
+#[derive(Serialize, Clone)]
+pub struct GameReport {
+    /// The deepest level reached
+    pub depth: i32,
+    /// Whether the bot was still alive when it ran out of turns
+    pub survived: bool,
+    pub stats: RunStats,
+}
+
+/// What the bot remembers from turn to turn
+#[derive(Default)]
+struct BotMemory {
+    /// Where the monster it's chasing was last seen
+    hunting: Option<Point>,
+    /// How many turns it has chased monsters without getting to hit one
+    chase_turns: i32,
+    /// Monsters it can't catch are left alone until this turn
+    ignore_until: i32,
+}
+
+/// Turns the bot chases monsters it can't reach before giving up on them
+const MAX_CHASE_TURNS: i32 = 20;
+/// Turns the bot leaves monsters be for after giving up on a chase
+const CHASE_COOLDOWN: i32 = 50;
+
+/// How a batch of games played by the bot went
+pub struct BatchReport {
+    pub games: Vec<GameReport>,
+}
+
+/// Plays a whole game from `seed` without a window, until the bot dies or
+/// runs out of turns
+pub fn play_game(seed: u64, config: &BotConfig) -> GameReport {
+    let mut gs = State::new();
+    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    register_storages(&mut gs.ecs);
+    gs.init_resources(seed);
+    gs.ecs.insert(Settings::default());
+    gs.ecs.insert(Headless {});
+    gs.generate_world_map(1);
+
+    let mut memory = BotMemory::default();
+    let mut runstate = RunState::PreRun;
+    loop {
+        runstate = match runstate {
+            RunState::PreRun => {
+                gs.run_systems();
+                RunState::AwaitingInput
+            }
+            RunState::MonsterTurn => gs.monster_turn(),
+            RunState::AwaitingInput => choose_action(&mut gs.ecs, config, &mut memory),
+            RunState::PlayerTurn => gs.player_turn(),
+            RunState::MagicMapReveal { row } => gs.reveal_map_row(row),
+            RunState::NextLevel => {
+                memory = BotMemory::default();
+                gs.goto_next_level();
+                RunState::PreRun
+            }
+            _ => break,
+        };
+        *gs.ecs.write_resource::<RunState>() = runstate;
+        delete_the_dead(&mut gs.ecs);
+        // Nobody is watching, so particles can go as soon as they appear
+        cull_dead_particles(&mut gs.ecs, f32::MAX);
+
+        runstate = *gs.ecs.fetch::<RunState>();
+        if runstate == RunState::GameOver || gs.ecs.fetch::<RunStats>().turns >= config.max_turns {
+            break;
+        }
+    }
+
+    let depth = gs.ecs.fetch::<Map>().depth;
+    let stats = (*gs.ecs.fetch::<RunStats>()).clone();
+    GameReport {
+        depth,
+        survived: runstate != RunState::GameOver,
+        stats,
+    }
+}
+
+/// Plays `count` games, seeded one after another from `first_seed`. Each
+/// game's report is passed to `on_game`, along with how many games have been
+/// played, as soon as it finishes
+pub fn play_games<F>(
+    first_seed: u64,
+    count: usize,
+    config: &BotConfig,
+    mut on_game: F,
+) -> BatchReport
+where
+    F: FnMut(usize, &GameReport),
+{
+    let games = (0..count)
+        .map(|i| {
+            let game = play_game(first_seed.wrapping_add(i as u64), config);
+            on_game(i + 1, &game);
+            game
+        })
+        .collect();
+    BatchReport { games }
+}
+
+/// Picks the bot's move: look after itself, then fight, loot, explore and
+/// finally descend
+fn choose_action(ecs: &mut World, config: &BotConfig, memory: &mut BotMemory) -> RunState {
+    if let Some(item) = item_to_use(ecs, config) {
+        use_item(ecs, item, None);
+        return RunState::PlayerTurn;
+    }
+
+    let player_pos = *ecs.fetch::<Point>();
+    let mut avoid = known_traps(ecs);
+    avoid.extend(shopkeepers(ecs));
+
+    // Fight whatever can be seen, nearest first, from afar if possible. If
+    // nothing is in sight, chase down the last thing that was, unless the
+    // chase has gone on too long
+    let turn = ecs.fetch::<RunStats>().turns;
+    let visible = visible_monsters(ecs);
+    let mut targets = visible.clone();
+    if targets.is_empty() {
+        if let Some(last_seen) = memory.hunting {
+            if last_seen != player_pos {
+                targets.push(last_seen);
+            }
+        }
+    }
+    for target in targets {
+        let distance = DistanceAlg::Pythagoras.distance2d(player_pos, target);
+        let in_sight = visible.contains(&target);
+        if in_sight && distance > 1.5 {
+            if let Some(scroll) = ranged_attack(ecs, distance) {
+                use_item(ecs, scroll, Some(target));
+                return RunState::PlayerTurn;
+            }
+        }
+        if !in_sight || distance > 1.5 {
+            if turn < memory.ignore_until {
+                continue;
+            }
+            memory.chase_turns += 1;
+            if memory.chase_turns > MAX_CHASE_TURNS {
+                memory.chase_turns = 0;
+                memory.ignore_until = turn + CHASE_COOLDOWN;
+                continue;
+            }
+        } else {
+            memory.chase_turns = 0;
+        }
+        let step = {
+            let map = ecs.fetch::<Map>();
+            let target_idx = map.xy_idx(target.x, target.y);
+            first_step(&map, player_pos, &avoid, |idx| idx == target_idx)
+        };
+        if let Some(step) = step {
+            memory.hunting = Some(target);
+            try_move_player(step.x, step.y, ecs);
+            return RunState::PlayerTurn;
+        }
+    }
+    memory.hunting = None;
+
+    // Pick up anything here, then go after anything in sight
+    let items = visible_items(ecs);
+    if let Some((item, _)) = items.iter().find(|(_, pos)| *pos == player_pos) {
+        let player_entity = *ecs.fetch::<Entity>();
+        ecs.write_storage::<WantsToPickupItem>()
+            .insert(
+                player_entity,
+                WantsToPickupItem {
+                    collected_by: player_entity,
+                    item: *item,
+                },
+            )
+            .expect("Unable to insert want to pickup");
+        return RunState::PlayerTurn;
+    }
+    let step = {
+        let map = ecs.fetch::<Map>();
+        let item_tiles: HashSet<usize> = items
+            .iter()
+            .map(|(_, pos)| map.xy_idx(pos.x, pos.y))
+            .collect();
+        first_step(&map, player_pos, &avoid, |idx| item_tiles.contains(&idx))
+    };
+    if let Some(step) = step {
+        try_move_player(step.x, step.y, ecs);
+        return RunState::PlayerTurn;
+    }
+
+    // Explore until the level's been seen or the bot has lingered too long,
+    // as long as it knows where the stairs are
+    let (stairs_known, on_stairs, turns_here) = {
+        let map = ecs.fetch::<Map>();
+        let stats = ecs.fetch::<RunStats>();
+        let stairs_known = map
+            .tiles
+            .iter()
+            .zip(map.revealed_tiles.iter())
+            .any(|(tile, revealed)| *tile == TileType::DownStairs && *revealed);
+        let on_stairs = map.tiles[map.xy_idx(player_pos.x, player_pos.y)] == TileType::DownStairs;
+        let turns_here = *stats.turns_per_depth.get(&map.depth).unwrap_or(&0);
+        (stairs_known, on_stairs, turns_here)
+    };
+    if !stairs_known || turns_here < config.max_turns_per_depth {
+        let step = {
+            let map = ecs.fetch::<Map>();
+            first_step(&map, player_pos, &avoid, |idx| is_frontier(&map, idx))
+        };
+        if let Some(step) = step {
+            try_move_player(step.x, step.y, ecs);
+            return RunState::PlayerTurn;
+        }
+    }
+
+    if on_stairs {
+        return RunState::NextLevel;
+    }
+    let step = {
+        let map = ecs.fetch::<Map>();
+        first_step(&map, player_pos, &avoid, |idx| {
+            map.tiles[idx] == TileType::DownStairs
+        })
+    };
+    if let Some(step) = step {
+        try_move_player(step.x, step.y, ecs);
+    }
+
+    // Otherwise there's nothing to do but wait
+    RunState::PlayerTurn
+}
+
+/// Returns a carried item worth using now: a healing potion when hurt, food
+/// when hungry, or equipment better than what's worn
+fn item_to_use(ecs: &World, config: &BotConfig) -> Option<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let hunger_clocks = ecs.read_storage::<HungerClock>();
+    let healing = ecs.read_storage::<ProvidesHealing>();
+    let food = ecs.read_storage::<ProvidesFood>();
+    let equippable = ecs.read_storage::<Equippable>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let melee_bonuses = ecs.read_storage::<MeleePowerBonus>();
+    let defense_bonuses = ecs.read_storage::<DefenseBonus>();
+    let cursed = ecs.read_storage::<Cursed>();
+    let two_handed = ecs.read_storage::<TwoHanded>();
+
+    let carried: Vec<Entity> = (&entities, &backpack)
+        .join()
+        .filter(|(_, pack)| pack.owner == *player_entity)
+        .map(|(entity, _)| entity)
+        .collect();
+
+    if let Some(stats) = combat_stats.get(*player_entity) {
+        if (stats.hp as f32) < stats.max_hp as f32 * config.heal_below {
+            if let Some(potion) = carried.iter().find(|e| healing.get(**e).is_some()) {
+                return Some(*potion);
+            }
+        }
+    }
+
+    if let Some(clock) = hunger_clocks.get(*player_entity) {
+        if clock.state == HungerState::Hungry || clock.state == HungerState::Starving {
+            if let Some(ration) = carried.iter().find(|e| food.get(**e).is_some()) {
+                return Some(*ration);
+            }
+        }
+    }
+
+    let bonus = |item: Entity| {
+        melee_bonuses.get(item).map_or(0, |b| b.power)
+            + defense_bonuses.get(item).map_or(0, |b| b.defense)
+    };
+    let wielding_two_handed = (&entities, &equipped).join().any(|(worn, eq)| {
+        eq.owner == *player_entity
+            && eq.slot == EquipmentSlot::Melee
+            && two_handed.get(worn).is_some()
+    });
+    for item in carried.iter() {
+        if let Some(can_equip) = equippable.get(*item) {
+            // Anything beats an empty slot, as long as nothing has to make
+            // room for it
+            let slots = can_equip.slot.fits();
+            let worn: Vec<Entity> = (&entities, &equipped)
+                .join()
+                .filter(|(_, eq)| eq.owner == *player_entity && slots.contains(&eq.slot))
+                .map(|(worn, _)| worn)
+                .collect();
+            let hands_full = can_equip.slot == EquipmentSlot::Shield && wielding_two_handed;
+            if worn.len() < slots.len() && !hands_full && two_handed.get(*item).is_none() {
+                return Some(*item);
+            }
+            // Equipping swaps out whatever is in the item's own slot, and the
+            // other hand if either weapon needs both. Cursed gear can't be
+            // swapped out
+            let swapped: Vec<Entity> = (&entities, &equipped)
+                .join()
+                .filter(|(worn, eq)| {
+                    eq.owner == *player_entity
+                        && (eq.slot == can_equip.slot
+                            || (two_handed.get(*item).is_some()
+                                && eq.slot == EquipmentSlot::Shield)
+                            || (can_equip.slot == EquipmentSlot::Shield
+                                && two_handed.get(*worn).is_some()))
+                })
+                .map(|(worn, _)| worn)
+                .collect();
+            if swapped.iter().any(|worn| cursed.get(*worn).is_some()) {
+                continue;
+            }
+            if bonus(*item) > swapped.iter().map(|worn| bonus(*worn)).sum::<i32>() {
+                return Some(*item);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns a carried damaging item that can reach `distance`, without
+/// catching the player in its blast
+fn ranged_attack(ecs: &World, distance: f32) -> Option<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let ranged = ecs.read_storage::<Ranged>();
+    let damaging = ecs.read_storage::<InflictsDamage>();
+    let areas = ecs.read_storage::<AreaOfEffect>();
+
+    (&entities, &backpack, &ranged, &damaging)
+        .join()
+        .find(|(entity, pack, range, _)| {
+            let blast = areas.get(*entity).map_or(0, |aoe| aoe.radius) as f32;
+            pack.owner == *player_entity && distance <= range.range as f32 && distance > blast + 1.0
+        })
+        .map(|(entity, _, _, _)| entity)
+}
+
+fn use_item(ecs: &mut World, item: Entity, target: Option<Point>) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<WantsToUseItem>()
+        .insert(player_entity, WantsToUseItem { item, target })
+        .expect("Unable to insert intent");
+}
+
+/// Where the monsters the player can see are, nearest first
+fn visible_monsters(ecs: &World) -> Vec<Point> {
+    let map = ecs.fetch::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+    let monsters = ecs.read_storage::<MonsterAI>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let positions = ecs.read_storage::<Position>();
+
+    let mut seen: Vec<Point> = (&monsters, &combat_stats, &positions)
+        .join()
+        .filter(|(_, stats, pos)| stats.hp > 0 && map.visible_tiles[map.xy_idx(pos.x, pos.y)])
+        .map(|(_, _, pos)| Point::new(pos.x, pos.y))
+        .collect();
+    seen.sort_by_key(|pos| {
+        (DistanceAlg::PythagorasSquared.distance2d(*player_pos, *pos) * 100.0) as i32
+    });
+    seen
+}
+
+/// Items lying where the player can see them and light enough to carry.
+/// Only food and healing are worth being weighed down for
+fn visible_items(ecs: &World) -> Vec<(Entity, Point)> {
+    let map = ecs.fetch::<Map>();
+    let player_entity = *ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let food = ecs.read_storage::<ProvidesFood>();
+    let healing = ecs.read_storage::<ProvidesHealing>();
+    let encumbrances = ecs.read_storage::<Encumbrance>();
+    let weights = ecs.read_storage::<Weight>();
+    let stacks = ecs.read_storage::<Stackable>();
+
+    let stays_unburdened = |item: Entity| match encumbrances.get(player_entity) {
+        Some(encumbrance) => {
+            let load = encumbrance.load + stack_weight(weights.get(item), stacks.get(item));
+            EncumbranceLevel::for_load(load, encumbrance.capacity) == EncumbranceLevel::Unencumbered
+        }
+        None => true,
+    };
+
+    (&entities, &items, &positions)
+        .join()
+        .filter(|(_, _, pos)| map.visible_tiles[map.xy_idx(pos.x, pos.y)])
+        .filter(|(entity, _, _)| can_carry(ecs, player_entity, *entity))
+        .filter(|(entity, _, _)| {
+            food.get(*entity).is_some()
+                || healing.get(*entity).is_some()
+                || stays_unburdened(*entity)
+        })
+        .map(|(entity, _, pos)| (entity, Point::new(pos.x, pos.y)))
+        .collect()
+}
+
+/// Tiles with a trap the player knows about
+fn known_traps(ecs: &World) -> HashSet<usize> {
+    let map = ecs.fetch::<Map>();
+    let triggers = ecs.read_storage::<EntryTrigger>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let positions = ecs.read_storage::<Position>();
+
+    (&triggers, &positions, !&hidden)
+        .join()
+        .map(|(_, pos, _)| map.xy_idx(pos.x, pos.y))
+        .filter(|idx| map.revealed_tiles[*idx])
+        .collect()
+}
+
+/// Tiles with a shopkeeper standing on them. Bumping one opens their shop,
+/// which the bot doesn't use, so it walks around instead
+fn shopkeepers(ecs: &World) -> HashSet<usize> {
+    let map = ecs.fetch::<Map>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let positions = ecs.read_storage::<Position>();
+
+    (&vendors, &positions)
+        .join()
+        .map(|(_, pos)| map.xy_idx(pos.x, pos.y))
+        .collect()
+}
+
+/// Whether `idx` is a floor tile the player knows, next to one they don't.
+/// The map's outer edge is always wall, so it's not worth exploring
+fn is_frontier(map: &Map, idx: usize) -> bool {
+    let x = idx as i32 % map.width;
+    let y = idx as i32 / map.width;
+    map.tiles[idx] != TileType::Wall
+        && STEPS.iter().any(|(dx, dy)| {
+            let (nx, ny) = (x + dx, y + dy);
+            nx > 0
+                && nx < map.width - 1
+                && ny > 0
+                && ny < map.height - 1
+                && !map.revealed_tiles[map.xy_idx(nx, ny)]
+        })
+}
+
+const STEPS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// Walks the known map outwards from `start` to the nearest tile `goal`
+/// accepts, keeping off `avoid` unless there's no other way. Returns the
+/// first step on the way
+fn first_step<F>(map: &Map, start: Point, avoid: &HashSet<usize>, goal: F) -> Option<Point>
+where
+    F: Fn(usize) -> bool,
+{
+    search(map, start, avoid, &goal).or_else(|| search(map, start, &HashSet::new(), &goal))
+}
+
+fn search<F>(map: &Map, start: Point, avoid: &HashSet<usize>, goal: &F) -> Option<Point>
+where
+    F: Fn(usize) -> bool,
+{
+    let start_idx = map.xy_idx(start.x, start.y);
+    let mut came_from: Vec<Option<usize>> = vec![None; map.tiles.len()];
+    let mut queue = VecDeque::new();
+    came_from[start_idx] = Some(start_idx);
+    queue.push_back(start_idx);
+
+    while let Some(idx) = queue.pop_front() {
+        if idx != start_idx && goal(idx) {
+            // Follow the trail back to the tile next to the start
+            let mut step = idx;
+            while came_from[step] != Some(start_idx) {
+                step = came_from[step]?;
+            }
+            let x = step as i32 % map.width;
+            let y = step as i32 / map.width;
+            return Some(Point::new(x - start.x, y - start.y));
+        }
+
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        for (dx, dy) in STEPS.iter() {
+            if !map.in_bounds(x + dx, y + dy) {
+                continue;
+            }
+            let next = map.xy_idx(x + dx, y + dy);
+            if came_from[next].is_none()
+                && map.revealed_tiles[next]
+                && map.tiles[next] != TileType::Wall
+                && !avoid.contains(&next)
+            {
+                came_from[next] = Some(idx);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+impl BatchReport {
+    /// Survival depths, causes of death and how items were used, as lines of
+    /// text
+    pub fn summary(&self) -> Vec<String> {
+        let count = self.games.len();
+        if count == 0 {
+            return vec!["No games played.".to_string()];
+        }
+        let percent = |n: usize| n as f32 * 100.0 / count as f32;
+
+        let survivors = self.games.iter().filter(|g| g.survived).count();
+        let total_depth: i32 = self.games.iter().map(|g| g.depth).sum();
+        let max_depth = self.games.iter().map(|g| g.depth).max().unwrap_or(0);
+        let mut lines = vec![
+            format!(
+                "Games played: {} ({} still alive at the turn limit)",
+                count, survivors
+            ),
+            format!(
+                "Survival depth: mean {:.2}, deepest {}",
+                total_depth as f32 / count as f32,
+                max_depth
+            ),
+        ];
+
+        let mut depths: BTreeMap<i32, usize> = BTreeMap::new();
+        for game in self.games.iter() {
+            *depths.entry(game.depth).or_insert(0) += 1;
+        }
+        for (depth, n) in depths.iter() {
+            lines.push(format!(
+                "  Depth {:>3}: {:>6} ({:.1}%)",
+                depth,
+                n,
+                percent(*n)
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push("Causes of death:".to_string());
+        let mut causes: BTreeMap<&str, usize> = BTreeMap::new();
+        for game in self.games.iter().filter(|g| !g.survived) {
+            let cause = match &game.stats.cause_of_death {
+                Some(cause) => cause.as_str(),
+                None => "something unknown",
+            };
+            *causes.entry(cause).or_insert(0) += 1;
+        }
+        let mut causes: Vec<(&str, usize)> = causes.into_iter().collect();
+        causes.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        for (cause, n) in causes.iter() {
+            lines.push(format!("  {:<40} {:>6} ({:.1}%)", cause, n, percent(*n)));
+        }
+
+        // An item is useful if the games that used it tended to go deeper
+        lines.push(String::new());
+        lines.push("Items:".to_string());
+        lines.push(format!(
+            "  {:<30} {:>8} {:>8} {:>12}",
+            "Item", "Found", "Used", "Mean depth"
+        ));
+        let mut items: BTreeMap<&str, (i32, i32, i32, i32)> = BTreeMap::new();
+        for game in self.games.iter() {
+            for (name, n) in game.stats.items_found.iter() {
+                items.entry(name).or_insert((0, 0, 0, 0)).0 += n;
+            }
+            for (name, n) in game.stats.items_used.iter() {
+                let entry = items.entry(name).or_insert((0, 0, 0, 0));
+                entry.1 += n;
+                entry.2 += game.depth;
+                entry.3 += 1;
+            }
+        }
+        for (name, (found, used, depth_sum, games)) in items.iter() {
+            let mean_depth = if *games > 0 {
+                format!("{:.2}", *depth_sum as f32 / *games as f32)
+            } else {
+                "-".to_string()
+            };
+            lines.push(format!(
+                "  {:<30} {:>8} {:>8} {:>12}",
+                name, found, used, mean_depth
+            ));
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,206 @@
+use super::{
+    apply_light, tile_glyph, Hidden, Map, Name, Position, RenderMode, Renderable, SpriteCell,
+    TileSet, ASCII_CONSOLE, ENTITY_CONSOLE, OVERLAY_CONSOLE, TERRAIN_CONSOLE,
+};
+use rltk::{Point, Rltk, RGB, RGBA};
+use specs::prelude::*;
+
+/// Width of the part of the screen the map is drawn in
+pub const VIEW_WIDTH: i32 = 80;
+/// Height of the part of the screen the map is drawn in, above the UI
+pub const VIEW_HEIGHT: i32 = 43;
+/// Whether to mark the space outside the map so the edge of the level is clear
+const SHOW_BOUNDARIES: bool = true;
+
+/// Returns the map coordinates of the top-left and bottom-right (exclusive)
+/// corners of the view, centred on the player
+pub fn get_screen_bounds(ecs: &World) -> (i32, i32, i32, i32) {
+    let player_pos = ecs.fetch::<Point>();
+    let min_x = player_pos.x - VIEW_WIDTH / 2;
+    let min_y = player_pos.y - VIEW_HEIGHT / 2;
+    (min_x, min_y, min_x + VIEW_WIDTH, min_y + VIEW_HEIGHT)
+}
+
+/// Converts a screen position to the map position shown there
+pub fn screen_to_world(ecs: &World, screen: Point) -> Point {
+    let (min_x, min_y, _, _) = get_screen_bounds(ecs);
+    Point::new(screen.x + min_x, screen.y + min_y)
+}
+
+/// Converts a map position to where it is drawn on screen, if it's in view
+pub fn world_to_screen(ecs: &World, world: Point) -> Option<Point> {
+    let (min_x, min_y, max_x, max_y) = get_screen_bounds(ecs);
+    if world.x < min_x || world.x >= max_x || world.y < min_y || world.y >= max_y {
+        return None;
+    }
+    Some(Point::new(world.x - min_x, world.y - min_y))
+}
+
+/// Draws the part of the map and the entities on it that are in view
+pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
+    match *ecs.fetch::<RenderMode>() {
+        RenderMode::Ascii => render_ascii(ecs, ctx),
+        RenderMode::Tiles => render_tiles(ecs, ctx),
+    }
+}
+
+fn render_ascii(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let (min_x, min_y, max_x, max_y) = get_screen_bounds(ecs);
+
+    for (screen_y, y) in (min_y..max_y).enumerate() {
+        for (screen_x, x) in (min_x..max_x).enumerate() {
+            if map.in_bounds(x, y) {
+                let idx = map.xy_idx(x, y);
+                if map.revealed_tiles[idx] {
+                    let (glyph, fg, bg) = tile_glyph(idx, &map);
+                    ctx.set(screen_x, screen_y, fg, bg, glyph);
+                }
+            } else if SHOW_BOUNDARIES {
+                ctx.set(
+                    screen_x,
+                    screen_y,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    rltk::to_cp437('·'),
+                );
+            }
+        }
+    }
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
+
+    let mut data = (&positions, &renderables, !&hidden)
+        .join()
+        .collect::<Vec<_>>();
+    data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
+    for (pos, render, _) in data.iter() {
+        if !map.in_bounds(pos.x, pos.y) {
+            continue;
+        }
+        let idx = map.xy_idx(pos.x, pos.y);
+        if map.visible_tiles[idx] {
+            if let Some(screen) = world_to_screen(ecs, Point::new(pos.x, pos.y)) {
+                let fg = apply_light(render.fg, map.light[idx]);
+                ctx.set(screen.x, screen.y, fg, render.bg, render.glyph);
+            }
+        }
+    }
+}
+
+/// Draws as much of a map as fits on screen, from its top-left corner. Used
+/// to play back map generation, where there is no player to centre on
+pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
+    for y in 0..i32::min(map.height, VIEW_HEIGHT) {
+        for x in 0..i32::min(map.width, VIEW_WIDTH) {
+            let idx = map.xy_idx(x, y);
+            if map.revealed_tiles[idx] {
+                let (glyph, fg, bg) = tile_glyph(idx, map);
+                ctx.set(x, y, fg, bg, glyph);
+            }
+        }
+    }
+}
+
+/// Draws terrain and entities with sprites from the `TileSet`, falling back to
+/// their glyphs on the ASCII console for anything without one
+fn render_tiles(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let tileset = ecs.fetch::<TileSet>();
+    let cell = *ecs.fetch::<SpriteCell>();
+    let (min_x, min_y, max_x, max_y) = get_screen_bounds(ecs);
+
+    for (screen_y, y) in (min_y..max_y).enumerate() {
+        for (screen_x, x) in (min_x..max_x).enumerate() {
+            if !map.in_bounds(x, y) {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            if !map.revealed_tiles[idx] {
+                continue;
+            }
+            match tileset.terrain_sprite(&map, idx) {
+                Some(sprite) => {
+                    ctx.set_active_console(TERRAIN_CONSOLE);
+                    ctx.add_sprite(
+                        cell.rect(screen_x as i32, screen_y as i32),
+                        0,
+                        terrain_tint(&map, idx),
+                        sprite,
+                    );
+                }
+                None => {
+                    let (glyph, fg, bg) = tile_glyph(idx, &map);
+                    ctx.set_active_console(ASCII_CONSOLE);
+                    ctx.set(screen_x, screen_y, fg, bg, glyph);
+                }
+            }
+        }
+    }
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let names = ecs.read_storage::<Name>();
+    let entities = ecs.entities();
+
+    let mut data = (&entities, &positions, &renderables, !&hidden)
+        .join()
+        .collect::<Vec<_>>();
+    data.sort_by_key(|&(_, _, render, _)| std::cmp::Reverse(render.render_order));
+    for (z_order, (entity, pos, render, _)) in data.iter().enumerate() {
+        if !map.in_bounds(pos.x, pos.y) {
+            continue;
+        }
+        let idx = map.xy_idx(pos.x, pos.y);
+        if !map.visible_tiles[idx] {
+            continue;
+        }
+        let screen = match world_to_screen(ecs, Point::new(pos.x, pos.y)) {
+            Some(screen) => screen,
+            None => continue,
+        };
+
+        let sprite = tileset.entity_sprite(
+            render.glyph,
+            names.get(*entity).map(|name| name.name.as_str()),
+        );
+        match sprite {
+            Some(sprite) => {
+                let light = apply_light(RGB::named(rltk::WHITE), map.light[idx]);
+                ctx.set_active_console(ENTITY_CONSOLE);
+                ctx.add_sprite(
+                    cell.rect(screen.x, screen.y),
+                    z_order as i32,
+                    RGBA::from_f32(light.r, light.g, light.b, 1.0),
+                    sprite,
+                );
+            }
+            None => {
+                // The ASCII console is under the terrain sprites, so draw
+                // these on the overlay to keep them from being hidden
+                let fg = apply_light(render.fg, map.light[idx]);
+                ctx.set_active_console(OVERLAY_CONSOLE);
+                ctx.set(screen.x, screen.y, fg, render.bg, render.glyph);
+            }
+        }
+    }
+
+    ctx.set_active_console(ASCII_CONSOLE);
+}
+
+/// Colors a terrain sprite the way `tile_glyph` colors its glyph
+fn terrain_tint(map: &Map, idx: usize) -> RGBA {
+    let mut tint = RGB::named(rltk::WHITE);
+    if map.visible_tiles[idx] {
+        if map.bloodstains.contains(&idx) {
+            tint = RGB::from_f32(1.0, 0.4, 0.4);
+        }
+        tint = apply_light(tint, map.light[idx]);
+    } else {
+        tint = RGB::from_f32(0.4, 0.4, 0.4);
+    }
+    RGBA::from_f32(tint.r, tint.g, tint.b, 1.0)
+}
@@ -50,7 +50,7 @@ pub struct CombatStats {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
-#[derive(Component, ConvertSaveload, Clone)]
+#[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Renderable {
     pub glyph: rltk::FontCharType,
     pub fg: RGB,
@@ -70,18 +70,40 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+/// Who or what dealt some damage, to credit kills and name causes of death
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DamageSource {
+    /// e.g. "a Goblin", "starvation"
+    pub cause: String,
+    pub by_player: bool,
+}
+
+impl DamageSource {
+    pub fn new<S: ToString>(cause: S, by_player: bool) -> DamageSource {
+        DamageSource {
+            cause: cause.to_string(),
+            by_player,
+        }
+    }
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    pub amount: Vec<(i32, DamageSource)>,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
+    pub fn new_damage(
+        store: &mut WriteStorage<SufferDamage>,
+        victim: Entity,
+        amount: i32,
+        source: DamageSource,
+    ) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering.amount.push((amount, source));
         } else {
             let dmg = SufferDamage {
-                amount: vec![amount],
+                amount: vec![(amount, source)],
             };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
@@ -146,30 +168,87 @@ pub struct SerializeMe;
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
     pub map: super::map::Map,
+    pub stats: super::RunStats,
+    #[serde(default)]
+    pub identities: super::ItemIdentities,
 }
 
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum EquipmentSlot {
     Melee,
     Shield,
+    Body,
+    Head,
+    Hands,
+    Feet,
+    LeftRing,
+    RightRing,
+    Amulet,
+}
+
+impl EquipmentSlot {
+    /// Every slot, in the order the paper doll lists them
+    pub const ALL: [EquipmentSlot; 9] = [
+        EquipmentSlot::Head,
+        EquipmentSlot::Amulet,
+        EquipmentSlot::Body,
+        EquipmentSlot::Melee,
+        EquipmentSlot::Shield,
+        EquipmentSlot::Hands,
+        EquipmentSlot::LeftRing,
+        EquipmentSlot::RightRing,
+        EquipmentSlot::Feet,
+    ];
+
+    /// Name shown next to the slot on the paper doll
+    pub fn label(self) -> &'static str {
+        match self {
+            EquipmentSlot::Melee => "Weapon",
+            EquipmentSlot::Shield => "Off hand",
+            EquipmentSlot::Body => "Body",
+            EquipmentSlot::Head => "Head",
+            EquipmentSlot::Hands => "Hands",
+            EquipmentSlot::Feet => "Feet",
+            EquipmentSlot::LeftRing => "Left ring",
+            EquipmentSlot::RightRing => "Right ring",
+            EquipmentSlot::Amulet => "Amulet",
+        }
+    }
+
+    /// The slots an item made for this one can be worn in, in the order
+    /// they're filled
+    pub fn fits(self) -> &'static [EquipmentSlot] {
+        match self {
+            EquipmentSlot::LeftRing | EquipmentSlot::RightRing => {
+                &[EquipmentSlot::LeftRing, EquipmentSlot::RightRing]
+            }
+            EquipmentSlot::Melee => &[EquipmentSlot::Melee],
+            EquipmentSlot::Shield => &[EquipmentSlot::Shield],
+            EquipmentSlot::Body => &[EquipmentSlot::Body],
+            EquipmentSlot::Head => &[EquipmentSlot::Head],
+            EquipmentSlot::Hands => &[EquipmentSlot::Hands],
+            EquipmentSlot::Feet => &[EquipmentSlot::Feet],
+            EquipmentSlot::Amulet => &[EquipmentSlot::Amulet],
+        }
+    }
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Equippable {
     pub slot: EquipmentSlot,
 }
-#[derive(Component, ConvertSaveload, Clone)]
+#[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Equipped {
     pub owner: Entity,
     pub slot: EquipmentSlot,
 }
 
-#[derive(Component, ConvertSaveload, Clone)]
+#[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct MeleePowerBonus {
     pub power: i32,
 }
 
-#[derive(Component, ConvertSaveload, Clone)]
+#[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct DefenseBonus {
     pub defense: i32,
 }
@@ -183,7 +262,7 @@ pub struct WantsToRemoveItem {
 pub struct ParticleLifetime {
     pub lifetime_ms: f32,
 }
-#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
 pub enum HungerState {
     WellFed,
     Normal,
@@ -203,6 +282,114 @@ pub struct ProvidesFood {}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct MagicMapper {}
 
+/// Reveals what every item in the user's backpack is
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct IdentifiesItems {}
+
+/// How far a piece of equipment's bonus strays from the usual for its kind.
+/// The player finds out by equipping or identifying it
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Enchantment {
+    pub bonus: i32,
+    pub known: bool,
+}
+
+/// Can't be unequipped until the curse is lifted
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Cursed {}
+
+/// Lifts the curses from everything the user carries and wears
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RemovesCurse {}
+
+/// Picking up more of the same item adds to this one's quantity instead of
+/// carrying another
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Stackable {
+    pub quantity: i32,
+}
+
+/// Uses left in a wand or staff, which crumbles once they run out
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Charges {
+    pub charges: i32,
+}
+
+/// How heavy an item is, in pounds. Stacks weigh this much for each item
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Weight {
+    pub weight: f32,
+}
+
+/// What an item is worth, in gold
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Value {
+    pub value: i32,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum EncumbranceLevel {
+    Unencumbered,
+    Burdened,
+    Stressed,
+}
+
+/// How much an entity is carrying against how much it can. Only entities
+/// with one of these are limited in what they pick up
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Encumbrance {
+    pub load: f32,
+    pub capacity: f32,
+    pub level: EncumbranceLevel,
+}
+
+/// A pile of coins lying about, which goes in the purse of whoever picks it
+/// up if they have one
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Gold {
+    pub amount: i32,
+}
+
+/// The gold an entity has to spend
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Purse {
+    pub gold: i32,
+}
+
+/// Sells what it carries and buys what the player offers, for as long as
+/// nobody attacks it
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Vendor {}
+
+/// A weapon that's been put to a whetstone, which only helps once
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Sharpened {}
+
+/// A weapon that takes both hands, leaving no room for a shield
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct TwoHanded {}
+
+/// A weapon light enough to fight with in the off hand, for a second, weaker
+/// attack
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct OffHand {}
+
+/// Heals whoever wears it a little every few turns
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Regeneration {
+    pub interval: i32,
+}
+
+/// Makes whoever wears it get hungry more slowly
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Sustenance {}
+
+/// Gives whoever wears it an extra move every few turns
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Speed {
+    pub interval: i32,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Hidden {}
 
@@ -228,3 +415,55 @@ pub struct PeriodicHiding {
 pub struct RevealChance {
     pub chance: i32,
 }
+
+/// Which faction an entity belongs to. How factions react to each other is
+/// looked up in the `FactionTable` resource
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Faction {
+    pub name: String,
+}
+
+/// Picks up items it walks over, equips upgrades and drinks healing potions
+/// when badly hurt
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ItemCollector {}
+
+/// Reads offensive scrolls from its backpack at hostile targets in range
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SpellCaster {}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum AwarenessState {
+    /// Does nothing until woken by noise or damage
+    Asleep,
+    /// Wanders after noises, but hasn't noticed its enemies
+    Unaware,
+    /// Has noticed its enemies and reacts to them
+    Aware,
+}
+
+/// How good an entity is at noticing things, and whether it has noticed its
+/// enemies yet
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Awareness {
+    pub state: AwarenessState,
+    pub perception: i32,
+}
+
+/// How hard an entity is to notice
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Stealth {
+    pub skill: i32,
+    pub sneaking: bool,
+}
+
+/// Lights up the tiles around it, fading out towards `range`
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct LightSource {
+    pub color: RGB,
+    pub range: i32,
+}
+
+/// Damage does nothing to this entity. Only the debug console hands it out
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct GodMode {}
@@ -0,0 +1,395 @@
+use super::{
+    can_carry, describe_item, gamelog::GameLog, hand_over, shift_load, spawn_named, split_stack,
+    EquipmentSlot, Equippable, Equipped, InBackpack, ItemIdentities, MeleePowerBonus, Name,
+    Position, Sharpened, Stackable,
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::BTreeMap;
+
+/// The recipes shipped with the game
+const RECIPES_JSON: &str = include_str!("../resources/recipes.json");
+
+/// What a recipe makes
+#[derive(Serialize, Deserialize, Clone)]
+pub enum Product {
+    /// A new item, by the name it spawns under
+    Item(String),
+    /// One more point of power on a melee weapon that hasn't had it yet
+    Sharpen,
+}
+
+/// One of the things a recipe uses up
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ingredient {
+    pub item: String,
+    pub count: i32,
+}
+
+/// Something the player can make from what they carry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Recipe {
+    pub name: String,
+    pub ingredients: Vec<Ingredient>,
+    pub product: Product,
+}
+
+impl Recipe {
+    /// Whether `counts` (items by name) holds everything the recipe needs
+    pub fn has_ingredients(&self, counts: &BTreeMap<String, i32>) -> bool {
+        self.ingredients
+            .iter()
+            .all(|i| counts.get(&i.item).copied().unwrap_or(0) >= i.count)
+    }
+}
+
+/// Every recipe there is, as loaded from the recipes file
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RecipeBook {
+    pub recipes: Vec<Recipe>,
+}
+
+/// Reads the recipes file built into the game
+pub fn load_recipes() -> RecipeBook {
+    serde_json::from_str(RECIPES_JSON).expect("Unable to parse recipes")
+}
+
+/// A recipe the player has everything for, and the weapon it's for if it
+/// works on one
+#[derive(Clone, Copy)]
+pub struct Craft {
+    pub recipe: usize,
+    pub target: Option<Entity>,
+}
+
+/// How many of each item the player has in their backpack, by name
+fn carried(ecs: &World) -> BTreeMap<String, i32> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let names = ecs.read_storage::<Name>();
+    let stackables = ecs.read_storage::<Stackable>();
+
+    let mut counts = BTreeMap::new();
+    for (item, pack, name) in (&ecs.entities(), &backpack, &names).join() {
+        if pack.owner == player_entity {
+            *counts.entry(name.name.clone()).or_insert(0) +=
+                stackables.get(item).map_or(1, |s| s.quantity);
+        }
+    }
+    counts
+}
+
+/// Melee weapons the player carries or wields that a whetstone would help
+fn blunt_weapons(ecs: &World) -> Vec<Entity> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let equippable = ecs.read_storage::<Equippable>();
+    let power = ecs.read_storage::<MeleePowerBonus>();
+    let sharpened = ecs.read_storage::<Sharpened>();
+
+    (&ecs.entities(), &equippable, &power, !&sharpened)
+        .join()
+        .filter(|(item, eq, _, _)| {
+            eq.slot == EquipmentSlot::Melee
+                && (matches!(backpack.get(*item), Some(pack) if pack.owner == player_entity)
+                    || matches!(equipped.get(*item), Some(worn) if worn.owner == player_entity))
+        })
+        .map(|(item, _, _, _)| item)
+        .collect()
+}
+
+/// Everything the player could make right now, in recipe order
+pub fn available_crafts(ecs: &World) -> Vec<Craft> {
+    let counts = carried(ecs);
+    let book = ecs.fetch::<RecipeBook>();
+    let mut crafts = Vec::new();
+    for (i, recipe) in book.recipes.iter().enumerate() {
+        if !recipe.has_ingredients(&counts) {
+            continue;
+        }
+        match recipe.product {
+            Product::Item(_) => crafts.push(Craft {
+                recipe: i,
+                target: None,
+            }),
+            Product::Sharpen => {
+                for weapon in blunt_weapons(ecs) {
+                    crafts.push(Craft {
+                        recipe: i,
+                        target: Some(weapon),
+                    });
+                }
+            }
+        }
+    }
+    crafts
+}
+
+/// How `craft` is listed in the crafting menu, with what it uses up
+pub fn craft_label(ecs: &World, craft: &Craft) -> String {
+    let book = ecs.fetch::<RecipeBook>();
+    let recipe = &book.recipes[craft.recipe];
+    let ingredients: Vec<String> = recipe
+        .ingredients
+        .iter()
+        .map(|i| format!("{}x {}", i.count, i.item))
+        .collect();
+    let name = match craft.target {
+        Some(target) => format!("{} {}", recipe.name, describe_item(ecs, target)),
+        None => recipe.name.to_string(),
+    };
+    format!("{} ({})", name, ingredients.join(", "))
+}
+
+/// Takes `count` of the items called `name` out of the player's backpack,
+/// from as many stacks as it takes, along with their weight
+fn use_up(ecs: &mut World, name: &str, count: i32) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let items: Vec<(Entity, i32)> = {
+        let backpack = ecs.read_storage::<InBackpack>();
+        let names = ecs.read_storage::<Name>();
+        let stackables = ecs.read_storage::<Stackable>();
+        (&ecs.entities(), &backpack, &names)
+            .join()
+            .filter(|(_, pack, n)| pack.owner == player_entity && n.name == name)
+            .map(|(item, _, _)| (item, stackables.get(item).map_or(1, |s| s.quantity)))
+            .collect()
+    };
+
+    let mut remaining = count;
+    for (item, quantity) in items {
+        if remaining < 1 {
+            break;
+        }
+        let used = if quantity > remaining {
+            split_stack(ecs, item, remaining)
+        } else {
+            item
+        };
+        remaining -= i32::min(quantity, remaining);
+        shift_load(ecs, player_entity, used, -1.0);
+        ecs.delete_entity(used).expect("Unable to delete");
+    }
+}
+
+/// Makes `craft`, using up its ingredients. Anything made that's too heavy
+/// to carry is left at the player's feet
+pub fn craft_item(ecs: &mut World, craft: &Craft) {
+    let recipe = ecs.fetch::<RecipeBook>().recipes[craft.recipe].clone();
+    for ingredient in recipe.ingredients.iter() {
+        use_up(ecs, &ingredient.item, ingredient.count);
+    }
+
+    match (&recipe.product, craft.target) {
+        (Product::Item(name), _) => {
+            let player_entity = *ecs.fetch::<Entity>();
+            let (x, y) = match ecs.read_storage::<Position>().get(player_entity) {
+                Some(pos) => (pos.x, pos.y),
+                None => return,
+            };
+            let item = match spawn_named(ecs, name, x, y) {
+                Some(item) => item,
+                None => return,
+            };
+            // Nobody makes a potion without knowing what it is
+            ecs.fetch_mut::<ItemIdentities>().identify(name);
+            let label = describe_item(ecs, item);
+            if !can_carry(ecs, player_entity, item) {
+                ecs.fetch_mut::<GameLog>().entries.push(format!(
+                    "You make the {}, but it's too heavy to carry and you set it down.",
+                    label
+                ));
+                return;
+            }
+            ecs.write_storage::<Position>().remove(item);
+            shift_load(ecs, player_entity, item, 1.0);
+            hand_over(ecs, item, player_entity);
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push(format!("You make the {}.", label));
+        }
+        (Product::Sharpen, Some(weapon)) => {
+            let label = describe_item(ecs, weapon);
+            if let Some(power) = ecs.write_storage::<MeleePowerBonus>().get_mut(weapon) {
+                power.power += 1;
+            }
+            ecs.write_storage::<Sharpened>()
+                .insert(weapon, Sharpened {})
+                .expect("Unable to insert sharpened");
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push(format!("You sharpen the {}.", label));
+        }
+        (Product::Sharpen, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawner, test_support::test_world, Encumbrance, Weight};
+
+    /// A world with the shipped recipes and `quantity` of each named item in
+    /// the player's backpack
+    fn world_carrying(items: &[(&str, i32)]) -> World {
+        let mut ecs = test_world();
+        ecs.insert(load_recipes());
+        let player = *ecs.fetch::<Entity>();
+        for (name, quantity) in items.iter() {
+            let item = spawner::spawn_named(&mut ecs, name, 1, 1).unwrap();
+            spawner::give_item(&mut ecs, player, item);
+            if let Some(stack) = ecs.write_storage::<Stackable>().get_mut(item) {
+                stack.quantity = *quantity;
+            }
+        }
+        ecs
+    }
+
+    fn recipe_names(ecs: &World) -> Vec<String> {
+        let book = ecs.fetch::<RecipeBook>();
+        available_crafts(ecs)
+            .iter()
+            .map(|craft| book.recipes[craft.recipe].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn the_shipped_recipes_load() {
+        let book = load_recipes();
+        assert!(!book.recipes.is_empty());
+        for recipe in book.recipes.iter() {
+            assert!(!recipe.ingredients.is_empty());
+            if let Product::Item(name) = &recipe.product {
+                assert!(spawner::SPAWN_NAMES.contains(&name.as_str()));
+            }
+        }
+    }
+
+    #[test]
+    fn a_recipe_needs_enough_of_every_ingredient() {
+        let recipe = Recipe {
+            name: "Stew".to_string(),
+            ingredients: vec![
+                Ingredient {
+                    item: "Rat Meat".to_string(),
+                    count: 2,
+                },
+                Ingredient {
+                    item: "Healing Herb".to_string(),
+                    count: 1,
+                },
+            ],
+            product: Product::Item("Rations".to_string()),
+        };
+        let counts = |meat, herbs| -> BTreeMap<String, i32> {
+            vec![
+                ("Rat Meat".to_string(), meat),
+                ("Healing Herb".to_string(), herbs),
+            ]
+            .into_iter()
+            .collect()
+        };
+        assert!(recipe.has_ingredients(&counts(2, 1)));
+        assert!(recipe.has_ingredients(&counts(5, 3)));
+        assert!(!recipe.has_ingredients(&counts(1, 1)));
+        assert!(!recipe.has_ingredients(&counts(2, 0)));
+        assert!(!recipe.has_ingredients(&BTreeMap::new()));
+    }
+
+    #[test]
+    fn only_what_can_be_made_is_offered() {
+        let ecs = world_carrying(&[("Healing Herb", 1)]);
+        assert!(recipe_names(&ecs).is_empty());
+
+        let ecs = world_carrying(&[("Healing Herb", 2)]);
+        assert_eq!(recipe_names(&ecs), vec!["Health Potion"]);
+
+        let ecs = world_carrying(&[("Healing Herb", 1), ("Rat Meat", 1)]);
+        assert_eq!(recipe_names(&ecs), vec!["Rations"]);
+    }
+
+    #[test]
+    fn sharpening_is_offered_for_each_blunt_weapon() {
+        let ecs = world_carrying(&[("Whetstone", 1)]);
+        assert!(recipe_names(&ecs).is_empty());
+
+        let ecs = world_carrying(&[("Whetstone", 1), ("Dagger", 1), ("Shield", 1)]);
+        assert_eq!(recipe_names(&ecs), vec!["Sharpen"]);
+
+        let ecs = world_carrying(&[("Whetstone", 1), ("Dagger", 1), ("Longsword", 1)]);
+        assert_eq!(recipe_names(&ecs), vec!["Sharpen", "Sharpen"]);
+    }
+
+    #[test]
+    fn crafting_uses_up_ingredients_and_gives_the_product() {
+        let mut ecs = world_carrying(&[("Healing Herb", 3)]);
+        ecs.insert(ItemIdentities::new(1));
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Encumbrance>()
+            .get_mut(player)
+            .unwrap()
+            .load = 0.3;
+        let craft = available_crafts(&ecs)[0];
+
+        craft_item(&mut ecs, &craft);
+        ecs.maintain();
+        let counts = carried(&ecs);
+        assert_eq!(counts.get("Healing Herb"), Some(&1));
+        assert_eq!(counts.get("Health Potion"), Some(&1));
+        assert!(ecs
+            .fetch::<ItemIdentities>()
+            .known
+            .contains("Health Potion"));
+
+        let potion_weight = ecs
+            .read_storage::<Weight>()
+            .join()
+            .map(|w| w.weight)
+            .fold(0.0, f32::max);
+        let load = ecs.read_storage::<Encumbrance>().get(player).unwrap().load;
+        assert!((load - (0.1 + potion_weight)).abs() < 0.001);
+    }
+
+    #[test]
+    fn a_product_too_heavy_to_carry_is_left_on_the_floor() {
+        let mut ecs = world_carrying(&[("Rat Meat", 2)]);
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Encumbrance>()
+            .get_mut(player)
+            .unwrap()
+            .load = 50.0;
+        let craft = available_crafts(&ecs)[0];
+
+        craft_item(&mut ecs, &craft);
+        ecs.maintain();
+        assert_eq!(carried(&ecs).get("Rations"), None);
+        let on_floor = (&ecs.read_storage::<Name>(), &ecs.read_storage::<Position>())
+            .join()
+            .any(|(name, _)| name.name == "Rations");
+        assert!(on_floor);
+    }
+
+    #[test]
+    fn a_weapon_can_only_be_sharpened_once() {
+        let mut ecs = world_carrying(&[("Whetstone", 2), ("Dagger", 1)]);
+        let craft = available_crafts(&ecs)[0];
+        let dagger = craft.target.unwrap();
+        let before = ecs
+            .read_storage::<MeleePowerBonus>()
+            .get(dagger)
+            .unwrap()
+            .power;
+
+        craft_item(&mut ecs, &craft);
+        ecs.maintain();
+        let after = ecs
+            .read_storage::<MeleePowerBonus>()
+            .get(dagger)
+            .unwrap()
+            .power;
+        assert_eq!(after, before + 1);
+        assert_eq!(carried(&ecs).get("Whetstone"), Some(&1));
+        assert!(available_crafts(&ecs).is_empty());
+    }
+}
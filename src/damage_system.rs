@@ -1,27 +1,115 @@
-use super::{CombatStats, GameLog, Map, Name, Player, Position, RunState, SufferDamage};
+use super::{
+    highscores, morgue, Awareness, AwarenessState, CombatStats, Equipped, Faction, GameLog,
+    GodMode, Headless, InBackpack, Map, MonsterAI, Name, Player, Position, RunState, RunStats,
+    SufferDamage, Vendor,
+};
 use specs::prelude::*;
 
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, Position>,
         WriteExpect<'a, Map>,
         Entities<'a>,
+        WriteStorage<'a, Awareness>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, RunStats>,
+        ReadStorage<'a, GodMode>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, Vendor>,
+        WriteStorage<'a, MonsterAI>,
+        WriteStorage<'a, Faction>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities) = data;
+        let (
+            mut stats,
+            mut damage,
+            positions,
+            mut map,
+            entities,
+            mut awareness,
+            players,
+            names,
+            mut run_stats,
+            god_modes,
+            mut log,
+            mut vendors,
+            mut monster_ai,
+            mut factions,
+        ) = data;
+
+        let mut angered: Vec<Entity> = Vec::new();
 
         for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
+            if god_modes.get(entity).is_some() {
+                continue;
+            }
+            for (amount, source) in damage.amount.iter() {
+                if players.get(entity).is_some() {
+                    *run_stats
+                        .damage_received
+                        .entry(source.cause.clone())
+                        .or_insert(0) += amount;
+                } else if source.by_player {
+                    *run_stats
+                        .damage_dealt
+                        .entry(source.cause.clone())
+                        .or_insert(0) += amount;
+                }
+
+                if source.by_player && vendors.get(entity).is_some() {
+                    angered.push(entity);
+                }
+
+                let was_alive = stats.hp > 0;
+                stats.hp -= amount;
+                // Whatever lands the killing blow gets the blame or the credit
+                if was_alive && stats.hp < 1 {
+                    if players.get(entity).is_some() {
+                        run_stats.cause_of_death = Some(source.cause.clone());
+                    } else if source.by_player {
+                        if let Some(name) = names.get(entity) {
+                            *run_stats.kills.entry(name.name.clone()).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
             let pos = positions.get(entity);
             if let Some(pos) = pos {
                 let idx = map.xy_idx(pos.x, pos.y);
                 map.bloodstains.insert(idx);
             }
+            // Nobody sleeps through being hurt
+            if let Some(aware) = awareness.get_mut(entity) {
+                aware.state = AwarenessState::Aware;
+            }
+        }
+
+        // A shopkeeper who gets hurt stops trading and starts fighting
+        for entity in angered {
+            if vendors.remove(entity).is_none() {
+                continue;
+            }
+            monster_ai
+                .insert(entity, MonsterAI::new())
+                .expect("Unable to insert AI");
+            factions
+                .insert(
+                    entity,
+                    Faction {
+                        name: "Angry Shopkeepers".to_string(),
+                    },
+                )
+                .expect("Unable to insert faction");
+            if let Some(name) = names.get(entity) {
+                log.entries.push(format!("The {} is furious!", name.name));
+            }
         }
 
         damage.clear();
@@ -30,6 +118,7 @@ impl<'a> System<'a> for DamageSystem {
 
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
+    let mut player_died = false;
     // Using a scope to make the borrow checker happy
     {
         let combat_stats = ecs.read_storage::<CombatStats>();
@@ -47,6 +136,9 @@ pub fn delete_the_dead(ecs: &mut World) {
                 match player {
                     Some(_) => {
                         let mut runstate = ecs.write_resource::<RunState>();
+                        if *runstate != RunState::GameOver {
+                            player_died = true;
+                        }
                         *runstate = RunState::GameOver;
                     }
                     None => {
@@ -61,7 +153,50 @@ pub fn delete_the_dead(ecs: &mut World) {
         }
     }
 
+    if player_died && ecs.try_fetch::<Headless>().is_none() {
+        morgue::create_morgue(ecs);
+        highscores::record_run(ecs);
+    }
+
+    drop_inventories(ecs, &dead);
+
     for victim in dead {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 }
+
+/// Drops everything carried or equipped by each of `victims` onto the floor
+/// where they stand
+fn drop_inventories(ecs: &mut World, victims: &[Entity]) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut backpack = ecs.write_storage::<InBackpack>();
+    let mut equipped = ecs.write_storage::<Equipped>();
+
+    let mut to_drop: Vec<(Entity, Position)> = Vec::new();
+    for victim in victims.iter() {
+        let victim_pos = match positions.get(*victim) {
+            Some(pos) => pos.clone(),
+            None => continue,
+        };
+
+        for (item, pack) in (&entities, &backpack).join() {
+            if pack.owner == *victim {
+                to_drop.push((item, victim_pos.clone()));
+            }
+        }
+        for (item, eq) in (&entities, &equipped).join() {
+            if eq.owner == *victim {
+                to_drop.push((item, victim_pos.clone()));
+            }
+        }
+    }
+
+    for (item, pos) in to_drop {
+        backpack.remove(item);
+        equipped.remove(item);
+        positions
+            .insert(item, pos)
+            .expect("Unable to insert position");
+    }
+}
@@ -0,0 +1,308 @@
+use super::*;
+use rltk::{Point, Rltk, VirtualKeyCode, RGB};
+
+/// How many log messages the console shows above its prompt
+const CONSOLE_LOG_LINES: usize = 8;
+
+/// What has been typed into the debug console so far
+#[derive(Default)]
+pub struct DebugConsole {
+    pub input: String,
+}
+
+const HELP: [&str; 9] = [
+    "spawn NAME - spawn anything the spawner knows at the cursor",
+    "teleport - move to the cursor",
+    "reveal - reveal the whole map",
+    "depth N - go to depth N",
+    "god - toggle god mode",
+    "hunger well_fed|normal|hungry|starving - set hunger",
+    "dump - list the components of what's under the cursor",
+    "inspect - open the entity inspector",
+    "help - show this list",
+];
+
+/// Draws the console over the game and reads a key of input, running the
+/// command when Enter is pressed
+pub fn debug_console(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let input = gs.ecs.fetch::<DebugConsole>().input.clone();
+
+    ctx.draw_box(0, 0, 79, CONSOLE_LOG_LINES as i32 + 3, white, black);
+    ctx.print_color(
+        2,
+        0,
+        RGB::named(rltk::YELLOW),
+        black,
+        "Debug console (` or Escape to close)",
+    );
+    {
+        let log = gs.ecs.fetch::<GameLog>();
+        let first = log.entries.len().saturating_sub(CONSOLE_LOG_LINES);
+        for (i, entry) in log.entries[first..].iter().enumerate() {
+            ctx.print_color(2, 1 + i as i32, RGB::named(rltk::GRAY), black, entry);
+        }
+    }
+    ctx.print_color(
+        2,
+        CONSOLE_LOG_LINES as i32 + 2,
+        white,
+        black,
+        format!("> {}_", input),
+    );
+
+    // Show which tile the cursor commands will act on
+    let mouse_pos = ctx.mouse_pos();
+    let cursor = screen_to_world(&gs.ecs, Point::new(mouse_pos.0, mouse_pos.1));
+    if let Some(screen) = world_to_screen(&gs.ecs, cursor) {
+        if screen.y > CONSOLE_LOG_LINES as i32 + 3 {
+            ctx.set_bg(screen.x, screen.y, RGB::named(rltk::MAGENTA));
+        }
+    }
+
+    let key = match ctx.key {
+        None => return RunState::ShowDebugConsole,
+        Some(key) => key,
+    };
+    match key {
+        VirtualKeyCode::Escape | VirtualKeyCode::Grave => RunState::AwaitingInput,
+        VirtualKeyCode::Back => {
+            gs.ecs.write_resource::<DebugConsole>().input.pop();
+            RunState::ShowDebugConsole
+        }
+        VirtualKeyCode::Return => {
+            gs.ecs.write_resource::<DebugConsole>().input.clear();
+            log(&mut gs.ecs, format!("> {}", input));
+            run_command(gs, &input, cursor)
+        }
+        _ => {
+            if let Some(c) = typed_char(key, ctx.shift) {
+                gs.ecs.write_resource::<DebugConsole>().input.push(c);
+            }
+            RunState::ShowDebugConsole
+        }
+    }
+}
+
+/// The character a key types, for the keys commands are written with
+fn typed_char(key: VirtualKeyCode, shift: bool) -> Option<char> {
+    match key {
+        VirtualKeyCode::Space => return Some(' '),
+        VirtualKeyCode::Minus => return Some(if shift { '_' } else { '-' }),
+        VirtualKeyCode::Period => return Some('.'),
+        _ => {}
+    }
+
+    // Letter and number keys are named after what they type
+    let name = key_name(key);
+    let c = name.chars().last()?;
+    if name.len() == 1 && c.is_ascii_alphabetic() {
+        Some(if shift { c } else { c.to_ascii_lowercase() })
+    } else if name.len() == 4 && name.starts_with("Key") && c.is_ascii_digit() {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+fn log(ecs: &mut World, message: String) {
+    ecs.write_resource::<GameLog>().entries.push(message);
+}
+
+/// Runs a console command, returning the state to go to afterwards
+fn run_command(gs: &mut State, input: &str, cursor: Point) -> RunState {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let (command, args) = match words.split_first() {
+        Some((command, args)) => (command.to_lowercase(), args),
+        None => return RunState::ShowDebugConsole,
+    };
+
+    match command.as_str() {
+        "help" => {
+            for line in HELP.iter() {
+                log(&mut gs.ecs, line.to_string());
+            }
+        }
+        "spawn" => spawn(&mut gs.ecs, &args.join(" "), cursor),
+        "teleport" => {
+            if teleport(&mut gs.ecs, cursor) {
+                return RunState::PreRun;
+            }
+        }
+        "reveal" => return RunState::MagicMapReveal { row: 0 },
+        "depth" => match args.first().and_then(|a| a.parse::<i32>().ok()) {
+            Some(depth) if depth > 0 => {
+                goto_depth(gs, depth);
+                gs.mapgen_next_state = Some(RunState::PreRun);
+                return RunState::MapGeneration;
+            }
+            _ => log(&mut gs.ecs, "Usage: depth N, from 1".to_string()),
+        },
+        "god" => toggle_god_mode(&mut gs.ecs),
+        "hunger" => set_hunger(&mut gs.ecs, args.first().copied().unwrap_or("")),
+        "dump" => dump(&mut gs.ecs, cursor),
+        "inspect" => {
+            return RunState::ShowInspector {
+                entity: None,
+                show_viewshed: false,
+                show_path: false,
+                scroll: 0,
+            }
+        }
+        _ => log(
+            &mut gs.ecs,
+            format!("Unknown command {}, try help", command),
+        ),
+    }
+    RunState::ShowDebugConsole
+}
+
+fn spawn(ecs: &mut World, name: &str, cursor: Point) {
+    if !ecs.fetch::<Map>().in_bounds(cursor.x, cursor.y) {
+        log(ecs, "The cursor isn't on the map.".to_string());
+        return;
+    }
+    let found = spawner::SPAWN_NAMES
+        .iter()
+        .find(|n| n.eq_ignore_ascii_case(name));
+    match found {
+        Some(found) => {
+            spawner::spawn_named(ecs, found, cursor.x, cursor.y);
+            log(
+                ecs,
+                format!("Spawned {} at {}, {}.", found, cursor.x, cursor.y),
+            );
+        }
+        None => log(
+            ecs,
+            format!(
+                "Nothing called {}, try: {}",
+                name,
+                spawner::SPAWN_NAMES.join(", ")
+            ),
+        ),
+    }
+}
+
+/// Moves the player to `target`, if it's somewhere they could stand
+fn teleport(ecs: &mut World, target: Point) -> bool {
+    let open = {
+        let map = ecs.fetch::<Map>();
+        map.in_bounds(target.x, target.y) && !map.blocked[map.xy_idx(target.x, target.y)]
+    };
+    if !open {
+        log(ecs, "Can't teleport there.".to_string());
+        return false;
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    if let Some(pos) = ecs.write_storage::<Position>().get_mut(player_entity) {
+        pos.x = target.x;
+        pos.y = target.y;
+    }
+    if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+        viewshed.dirty = true;
+    }
+    *ecs.write_resource::<Point>() = target;
+    log(ecs, format!("Teleported to {}, {}.", target.x, target.y));
+    true
+}
+
+/// Leaves the current level for a new one at `depth`, as taking the stairs
+/// does
+fn goto_depth(gs: &mut State, depth: i32) {
+    let to_delete = gs.entities_to_remove_on_level_change();
+    for target in to_delete {
+        gs.ecs
+            .delete_entity(target)
+            .expect("Unable to delete entity");
+    }
+    gs.generate_world_map(depth);
+    log(&mut gs.ecs, format!("Went to depth {}.", depth));
+}
+
+fn toggle_god_mode(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let enabled = {
+        let mut god_modes = ecs.write_storage::<GodMode>();
+        if god_modes.remove(player_entity).is_some() {
+            false
+        } else {
+            god_modes
+                .insert(player_entity, GodMode {})
+                .expect("Unable to insert god mode");
+            true
+        }
+    };
+    log(
+        ecs,
+        format!("God mode {}.", if enabled { "on" } else { "off" }),
+    );
+}
+
+fn set_hunger(ecs: &mut World, state: &str) {
+    let state = match state {
+        "well_fed" => HungerState::WellFed,
+        "normal" => HungerState::Normal,
+        "hungry" => HungerState::Hungry,
+        "starving" => HungerState::Starving,
+        _ => {
+            log(
+                ecs,
+                "Usage: hunger well_fed|normal|hungry|starving".to_string(),
+            );
+            return;
+        }
+    };
+    let player_entity = *ecs.fetch::<Entity>();
+    if let Some(clock) = ecs.write_storage::<HungerClock>().get_mut(player_entity) {
+        clock.state = state;
+        clock.duration = 200;
+    }
+    log(ecs, format!("Hunger set to {:?}.", state));
+}
+
+/// Logs which components each entity under the cursor has
+fn dump(ecs: &mut World, cursor: Point) {
+    let lines = {
+        let map = ecs.fetch::<Map>();
+        if !map.in_bounds(cursor.x, cursor.y) {
+            vec!["The cursor isn't on the map.".to_string()]
+        } else {
+            let entities = ecs.entities();
+            let positions = ecs.read_storage::<Position>();
+            let names = ecs.read_storage::<Name>();
+            let here: Vec<Entity> = (&entities, &positions)
+                .join()
+                .filter(|(_, pos)| pos.x == cursor.x && pos.y == cursor.y)
+                .map(|(entity, _)| entity)
+                .collect();
+            if here.is_empty() {
+                vec![format!("Nothing at {}, {}.", cursor.x, cursor.y)]
+            } else {
+                here.iter()
+                    .map(|entity| {
+                        let name = match names.get(*entity) {
+                            Some(name) => name.name.clone(),
+                            None => "Unnamed".to_string(),
+                        };
+                        format!(
+                            "{} (entity {}): {}",
+                            name,
+                            entity.id(),
+                            inspect_components(ecs, *entity)
+                                .iter()
+                                .map(|(name, _)| *name)
+                                .collect::<Vec<&str>>()
+                                .join(", ")
+                        )
+                    })
+                    .collect()
+            }
+        }
+    };
+    for line in lines {
+        log(ecs, line);
+    }
+}
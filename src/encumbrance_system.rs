@@ -0,0 +1,177 @@
+use super::{
+    gamelog::GameLog, CombatStats, Encumbrance, EncumbranceLevel, Equipped, InBackpack, Stackable,
+    Weight,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// How many pounds someone with `stats` can carry
+pub fn carry_capacity(stats: &CombatStats) -> f32 {
+    15.0 + stats.power as f32 * 5.0 + stats.max_hp as f32 / 3.0
+}
+
+/// What an item weighs, counting everything in its stack
+pub fn stack_weight(weight: Option<&Weight>, stack: Option<&Stackable>) -> f32 {
+    weight.map_or(0.0, |w| w.weight) * stack.map_or(1, |s| s.quantity) as f32
+}
+
+impl EncumbranceLevel {
+    /// Carrying over half of what you can works up an appetite, and over
+    /// three quarters slows you down too
+    pub fn for_load(load: f32, capacity: f32) -> EncumbranceLevel {
+        if load > capacity * 0.75 {
+            EncumbranceLevel::Stressed
+        } else if load > capacity * 0.5 {
+            EncumbranceLevel::Burdened
+        } else {
+            EncumbranceLevel::Unencumbered
+        }
+    }
+}
+
+/// Whether `entity` has room to pick up `item`. Anything without a carrying
+/// capacity can take whatever it likes
+pub fn can_carry(ecs: &World, entity: Entity, item: Entity) -> bool {
+    let weight = stack_weight(
+        ecs.read_storage::<Weight>().get(item),
+        ecs.read_storage::<Stackable>().get(item),
+    );
+    match ecs.read_storage::<Encumbrance>().get(entity) {
+        Some(encumbrance) => encumbrance.load + weight <= encumbrance.capacity,
+        None => true,
+    }
+}
+
+/// Weighs what everyone with a carrying capacity holds, letting the player
+/// know when their load starts or stops weighing on them
+pub struct EncumbranceSystem {}
+
+impl<'a> System<'a> for EncumbranceSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, Encumbrance>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Weight>,
+        ReadStorage<'a, Stackable>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            mut gamelog,
+            mut encumbrances,
+            combat_stats,
+            backpack,
+            equipped,
+            weights,
+            stackables,
+        ) = data;
+
+        let mut loads: HashMap<Entity, f32> = HashMap::new();
+        for (item, weight) in (&entities, &weights).join() {
+            let owner = match (backpack.get(item), equipped.get(item)) {
+                (Some(pack), _) => pack.owner,
+                (None, Some(worn)) => worn.owner,
+                (None, None) => continue,
+            };
+            *loads.entry(owner).or_insert(0.0) += stack_weight(Some(weight), stackables.get(item));
+        }
+
+        for (entity, encumbrance, stats) in (&entities, &mut encumbrances, &combat_stats).join() {
+            encumbrance.load = loads.get(&entity).copied().unwrap_or(0.0);
+            encumbrance.capacity = carry_capacity(stats);
+            let level = EncumbranceLevel::for_load(encumbrance.load, encumbrance.capacity);
+            if level != encumbrance.level && entity == *player_entity {
+                gamelog.entries.push(
+                    match level {
+                        EncumbranceLevel::Unencumbered => "Your load no longer weighs you down.",
+                        EncumbranceLevel::Burdened => "You are burdened by your load.",
+                        EncumbranceLevel::Stressed => "You strain under your load!",
+                    }
+                    .to_string(),
+                );
+            }
+            encumbrance.level = level;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawner, test_support::test_world};
+
+    #[test]
+    fn levels_change_past_half_and_three_quarters() {
+        let level = |load| EncumbranceLevel::for_load(load, 40.0);
+        assert!(level(0.0) == EncumbranceLevel::Unencumbered);
+        assert!(level(20.0) == EncumbranceLevel::Unencumbered);
+        assert!(level(20.5) == EncumbranceLevel::Burdened);
+        assert!(level(30.0) == EncumbranceLevel::Burdened);
+        assert!(level(30.5) == EncumbranceLevel::Stressed);
+    }
+
+    #[test]
+    fn stronger_and_hardier_means_more_capacity() {
+        let stats = |power, max_hp| CombatStats {
+            max_hp,
+            hp: max_hp,
+            defense: 0,
+            power,
+        };
+        assert_eq!(carry_capacity(&stats(5, 30)), 50.0);
+        assert!(carry_capacity(&stats(6, 30)) > carry_capacity(&stats(5, 30)));
+        assert!(carry_capacity(&stats(5, 60)) > carry_capacity(&stats(5, 30)));
+    }
+
+    #[test]
+    fn stacks_weigh_as_much_as_everything_in_them() {
+        let weight = Weight { weight: 0.5 };
+        let stack = Stackable { quantity: 4 };
+        assert_eq!(stack_weight(Some(&weight), Some(&stack)), 2.0);
+        assert_eq!(stack_weight(Some(&weight), None), 0.5);
+        assert_eq!(stack_weight(None, Some(&stack)), 0.0);
+    }
+
+    #[test]
+    fn the_system_weighs_what_the_player_carries() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        for _ in 0..2 {
+            let mail = spawner::spawn_named(&mut ecs, "Chain Mail", 1, 1).unwrap();
+            spawner::give_item(&mut ecs, player, mail);
+        }
+        EncumbranceSystem {}.run_now(&ecs);
+
+        let encumbrances = ecs.read_storage::<Encumbrance>();
+        let encumbrance = encumbrances.get(player).unwrap();
+        let mail_weight = ecs
+            .read_storage::<Weight>()
+            .join()
+            .next()
+            .map(|w| w.weight)
+            .unwrap();
+        assert_eq!(encumbrance.load, mail_weight * 2.0);
+        assert!(encumbrance.level == EncumbranceLevel::for_load(encumbrance.load, 50.0));
+    }
+
+    #[test]
+    fn nothing_goes_in_a_full_pack() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let mail = spawner::spawn_named(&mut ecs, "Chain Mail", 1, 1).unwrap();
+        assert!(can_carry(&ecs, player, mail));
+
+        ecs.write_storage::<Encumbrance>()
+            .get_mut(player)
+            .unwrap()
+            .load = 49.5;
+        assert!(!can_carry(&ecs, player, mail));
+    }
+}
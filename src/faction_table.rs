@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+/// How an entity of one faction reacts upon seeing an entity of another
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Reaction {
+    Ignore,
+    Attack,
+    Flee,
+}
+
+/// Lookup table of how each faction reacts to each other faction.
+/// Pairs that were never added (and members of the same faction) default to
+/// `Reaction::Ignore`
+#[derive(Default)]
+pub struct FactionTable {
+    reactions: HashMap<(String, String), Reaction>,
+}
+
+impl FactionTable {
+    pub fn new() -> FactionTable {
+        FactionTable {
+            reactions: HashMap::new(),
+        }
+    }
+
+    /// Sets how members of `faction` react to members of `other`
+    pub fn add<S: ToString>(mut self, faction: S, other: S, reaction: Reaction) -> FactionTable {
+        self.reactions
+            .insert((faction.to_string(), other.to_string()), reaction);
+        self
+    }
+
+    pub fn reaction(&self, faction: &str, other: &str) -> Reaction {
+        if faction == other {
+            return Reaction::Ignore;
+        }
+
+        match self
+            .reactions
+            .get(&(faction.to_string(), other.to_string()))
+        {
+            Some(reaction) => *reaction,
+            None => Reaction::Ignore,
+        }
+    }
+}
+
+/// The reaction table used by the game
+pub fn default_faction_table() -> FactionTable {
+    FactionTable::new()
+        .add("Goblins", "Player", Reaction::Attack)
+        .add("Goblins", "Orcs", Reaction::Attack)
+        .add("Orcs", "Player", Reaction::Attack)
+        .add("Orcs", "Goblins", Reaction::Attack)
+        .add("Critters", "Player", Reaction::Flee)
+        .add("Critters", "Goblins", Reaction::Flee)
+        .add("Critters", "Orcs", Reaction::Flee)
+        .add("Angry Shopkeepers", "Player", Reaction::Attack)
+}
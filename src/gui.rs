@@ -1,7 +1,16 @@
 extern crate serde;
 use super::{
-    CombatStats, Equipped, GameLog, Hidden, HungerClock, HungerState, InBackpack, Map, Name,
-    Player, Position, RexAssets, RunState, State, Viewshed,
+    available_crafts, bloodstain_color, buy_price, craft_label, describe_item, format_date,
+    is_bindable, key_name, save_settings, screen_to_world, sell_price, tile_appearance,
+    world_to_screen, AutoPickup, Command, Craft, FontChoice, HighScoreSort, Morgue, PostEffect,
+    Purse, RunHistory, Settings, TileType, ALL_COMMANDS, ASCII_CONSOLE, LOG_LENGTHS,
+    MAX_WINDOW_SCALE, OVERLAY_CONSOLE, VIEW_HEIGHT,
+};
+use super::{
+    Awareness, AwarenessState, CombatStats, DefenseBonus, Encumbrance, EncumbranceLevel,
+    EquipmentSlot, Equipped, GameLog, Hidden, HungerClock, HungerState, InBackpack, ItemIdentities,
+    Map, MeleePowerBonus, Name, Player, Position, RexAssets, RunState, Stackable, State, Stealth,
+    Viewshed,
 };
 use rltk::{Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
@@ -12,6 +21,8 @@ pub enum MainMenuSelection {
     SaveGame,
     NewGame,
     LoadGame,
+    Options,
+    HighScores,
     Quit,
 }
 
@@ -34,9 +45,14 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let combat_stats = ecs.read_storage::<CombatStats>();
     let players = ecs.read_storage::<Player>();
     let hunger_clocks = ecs.read_storage::<HungerClock>();
+    let stealths = ecs.read_storage::<Stealth>();
+    let encumbrances = ecs.read_storage::<Encumbrance>();
+    let purses = ecs.read_storage::<Purse>();
     let log = ecs.fetch::<GameLog>();
 
-    for (_player, stats, hc) in (&players, &combat_stats, &hunger_clocks).join() {
+    for (player_entity, _player, stats, hc) in
+        (&ecs.entities(), &players, &combat_stats, &hunger_clocks).join()
+    {
         let health = format!(" HP: {} / {} ", stats.hp, stats.max_hp);
         ctx.print_color(
             12,
@@ -80,6 +96,46 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
                 "Starving",
             ),
         }
+
+        if let Some(purse) = purses.get(player_entity) {
+            ctx.print_color(
+                2,
+                42,
+                RGB::named(rltk::GOLD),
+                RGB::named(rltk::BLACK),
+                format!("Gold: {}", purse.gold),
+            );
+        }
+
+        match encumbrances.get(player_entity).map(|e| e.level) {
+            Some(EncumbranceLevel::Burdened) => ctx.print_color(
+                49,
+                42,
+                RGB::named(rltk::ORANGE),
+                RGB::named(rltk::BLACK),
+                "Burdened",
+            ),
+            Some(EncumbranceLevel::Stressed) => ctx.print_color(
+                49,
+                42,
+                RGB::named(rltk::RED),
+                RGB::named(rltk::BLACK),
+                "Stressed",
+            ),
+            _ => {}
+        }
+
+        if let Some(stealth) = stealths.get(player_entity) {
+            if stealth.sneaking {
+                ctx.print_color(
+                    60,
+                    42,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    "Sneaking",
+                );
+            }
+        }
     }
 
     let mut y = 44;
@@ -100,33 +156,56 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         &depth,
     );
 
+    // Highlights and tooltips go over the map, whichever way it's drawn
+    ctx.set_active_console(OVERLAY_CONSOLE);
     let mouse_pos = ctx.mouse_pos();
     ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::MAGENTA));
 
     draw_tooltips(ecs, ctx);
+    ctx.set_active_console(ASCII_CONSOLE);
 }
 
-fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
+/// Names of everything the player can see at `pos`
+fn visible_names_at(ecs: &World, pos: Point) -> Vec<String> {
     let map = ecs.fetch::<Map>();
     let names = ecs.read_storage::<Name>();
     let positions = ecs.read_storage::<Position>();
     let hidden = ecs.read_storage::<Hidden>();
+    let awareness = ecs.read_storage::<Awareness>();
+    let entities = ecs.entities();
 
-    let mouse_pos = ctx.mouse_pos();
+    let mut seen: Vec<String> = Vec::new();
 
-    if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
-        return;
+    for (entity, _name, position, _) in (&entities, &names, &positions, !&hidden).join() {
+        let idx = map.xy_idx(position.x, position.y);
+        if position.x == pos.x && position.y == pos.y && map.visible_tiles[idx] {
+            // Let the player know who can be snuck up on
+            let name = describe_item(ecs, entity);
+            match awareness.get(entity).map(|a| a.state) {
+                Some(AwarenessState::Asleep) => seen.push(format!("{} (asleep)", name)),
+                Some(AwarenessState::Unaware) => seen.push(format!("{} (unaware)", name)),
+                _ => seen.push(name),
+            }
+        }
     }
 
-    let mut tooltip: Vec<String> = Vec::new();
+    seen
+}
 
-    for (name, position, _) in (&names, &positions, !&hidden).join() {
-        let idx = map.xy_idx(position.x, position.y);
-        if position.x == mouse_pos.0 && position.y == mouse_pos.1 && map.visible_tiles[idx] {
-            tooltip.push(name.name.to_string());
-        }
+fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
+    let mouse_pos = ctx.mouse_pos();
+    let mouse_map_pos = screen_to_world(ecs, Point::new(mouse_pos.0, mouse_pos.1));
+
+    if mouse_pos.1 >= VIEW_HEIGHT
+        || !ecs
+            .fetch::<Map>()
+            .in_bounds(mouse_map_pos.x, mouse_map_pos.y)
+    {
+        return;
     }
 
+    let tooltip = visible_names_at(ecs, mouse_map_pos);
+
     if !tooltip.is_empty() {
         let mut width: i32 = 0;
         for s in tooltip.iter() {
@@ -211,6 +290,22 @@ pub enum ItemMenuResult {
     Selected,
 }
 
+/// How wide an item menu's box needs to be to fit the names of everything
+/// the player carries and wears
+fn item_menu_width(ecs: &World) -> i32 {
+    let player_entity = ecs.fetch::<Entity>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let equipped = ecs.read_storage::<Equipped>();
+    ecs.entities()
+        .join()
+        .filter(|item| {
+            matches!(backpack.get(*item), Some(pack) if pack.owner == *player_entity)
+                || matches!(equipped.get(*item), Some(worn) if worn.owner == *player_entity)
+        })
+        .map(|item| describe_item(ecs, item).len() as i32 + 7)
+        .fold(31, i32::max)
+}
+
 pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
@@ -227,7 +322,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     ctx.draw_box(
         15,
         y - 2,
-        31,
+        item_menu_width(&gs.ecs),
         (count + 3) as i32,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
@@ -238,7 +333,13 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
         y - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Inventory",
+        match gs.ecs.read_storage::<Encumbrance>().get(*player_entity) {
+            Some(encumbrance) => format!(
+                "Inventory ({:.1}/{:.0} lbs)",
+                encumbrance.load, encumbrance.capacity
+            ),
+            None => "Inventory".to_string(),
+        },
     );
 
     ctx.print_color(
@@ -252,7 +353,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     let mut equippable: Vec<Entity> = Vec::new();
     let mut j = 0;
 
-    for (entity, _pack, name) in (&entities, &backpack, &names)
+    for (entity, _pack, _name) in (&entities, &backpack, &names)
         .join()
         .filter(|item| item.1.owner == *player_entity)
     {
@@ -278,7 +379,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
             rltk::to_cp437(')'),
         );
 
-        ctx.print(21, y, &name.name.to_string());
+        ctx.print(21, y, describe_item(&gs.ecs, entity));
         equippable.push(entity);
         y += 1;
         j += 1;
@@ -317,7 +418,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     ctx.draw_box(
         15,
         y - 2,
-        31,
+        item_menu_width(&gs.ecs),
         (count + 3) as i32,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
@@ -339,7 +440,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
 
     let mut equippable: Vec<Entity> = Vec::new();
     let mut j = 0;
-    for (entity, _pack, name) in (&entities, &backpack, &names)
+    for (entity, _pack, _name) in (&entities, &backpack, &names)
         .join()
         .filter(|item| item.1.owner == *player_entity)
     {
@@ -365,7 +466,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
             rltk::to_cp437(')'),
         );
 
-        ctx.print(21, y, &name.name.to_string());
+        ctx.print(21, y, describe_item(&gs.ecs, entity));
         equippable.push(entity);
         y += 1;
         j += 1;
@@ -389,6 +490,77 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     }
 }
 
+/// Asks how many of the stack `item` to drop, out of however many there
+/// are. Returns the quantity chosen so far
+pub fn drop_quantity_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    item: Entity,
+    quantity: i32,
+) -> (ItemMenuResult, i32) {
+    let stack = gs
+        .ecs
+        .read_storage::<Stackable>()
+        .get(item)
+        .map_or(1, |s| s.quantity);
+    let name = gs
+        .ecs
+        .read_storage::<Name>()
+        .get(item)
+        .map(|n| gs.ecs.fetch::<ItemIdentities>().display_name(&n.name))
+        .unwrap_or_default();
+    let quantity = i32::max(1, i32::min(quantity, stack));
+
+    let width = i32::max(31, name.len() as i32 + 6);
+    ctx.draw_box(
+        15,
+        23,
+        width,
+        4,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        23,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Drop How Many?",
+    );
+    ctx.print(18, 24, &name);
+    ctx.print_color(
+        18,
+        25,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        format!("< {} > of {}", quantity, stack),
+    );
+    ctx.print_color(
+        18,
+        27,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ENTER to drop, ESCAPE to cancel",
+    );
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, quantity),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, quantity),
+            VirtualKeyCode::Return => (ItemMenuResult::Selected, quantity),
+            VirtualKeyCode::Left | VirtualKeyCode::Down => {
+                (ItemMenuResult::NoResponse, i32::max(1, quantity - 1))
+            }
+            VirtualKeyCode::Right | VirtualKeyCode::Up => {
+                (ItemMenuResult::NoResponse, i32::min(stack, quantity + 1))
+            }
+            VirtualKeyCode::Home => (ItemMenuResult::NoResponse, 1),
+            VirtualKeyCode::End => (ItemMenuResult::NoResponse, stack),
+            _ => (ItemMenuResult::NoResponse, quantity),
+        },
+    }
+}
+
 pub fn ranged_target(
     gs: &mut State,
     ctx: &mut Rltk,
@@ -398,6 +570,7 @@ pub fn ranged_target(
     let player_pos = gs.ecs.fetch::<Point>();
     let viewsheds = gs.ecs.read_storage::<Viewshed>();
 
+    ctx.set_active_console(OVERLAY_CONSOLE);
     ctx.print_color(
         5,
         0,
@@ -415,41 +588,113 @@ pub fn ranged_target(
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
 
             if distance <= range as f32 {
-                ctx.set_bg(idx.x, idx.y, RGB::named(rltk::BLUE));
+                if let Some(screen) = world_to_screen(&gs.ecs, *idx) {
+                    ctx.set_bg(screen.x, screen.y, RGB::named(rltk::BLUE));
+                }
                 available_cells.push(idx);
             }
         }
     } else {
+        ctx.set_active_console(ASCII_CONSOLE);
         return (ItemMenuResult::Cancel, None);
     }
 
     let mouse_pos = ctx.mouse_pos();
+    let mouse_map_pos = screen_to_world(&gs.ecs, Point::new(mouse_pos.0, mouse_pos.1));
     let mut valid_target = false;
 
     for idx in available_cells.iter() {
-        if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 {
+        if idx.x == mouse_map_pos.x && idx.y == mouse_map_pos.y {
             valid_target = true;
         }
     }
 
     if valid_target {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
-        if ctx.left_click {
-            return (
-                ItemMenuResult::Selected,
-                Some(Point::new(mouse_pos.0, mouse_pos.1)),
-            );
-        }
     } else {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
-        if ctx.left_click {
-            return (ItemMenuResult::Cancel, None);
+    }
+    ctx.set_active_console(ASCII_CONSOLE);
+
+    if ctx.left_click {
+        if valid_target {
+            return (ItemMenuResult::Selected, Some(mouse_map_pos));
         }
+        return (ItemMenuResult::Cancel, None);
     }
 
     (ItemMenuResult::NoResponse, None)
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum LookResult {
+    NoResponse { cursor: Point },
+    Close,
+}
+
+/// Lets the player move a cursor over the map with the movement keys and
+/// says what's under it
+pub fn look_mode(gs: &mut State, ctx: &mut Rltk, cursor: Point) -> LookResult {
+    let description = {
+        let map = gs.ecs.fetch::<Map>();
+        let idx = map.xy_idx(cursor.x, cursor.y);
+        let names = visible_names_at(&gs.ecs, cursor);
+        if !map.revealed_tiles[idx] {
+            "You haven't seen that far.".to_string()
+        } else if !names.is_empty() {
+            names.join(", ")
+        } else {
+            match map.tiles[idx] {
+                TileType::Wall => "A wall.",
+                TileType::Floor => "The floor.",
+                TileType::DownStairs => "Stairs leading down.",
+            }
+            .to_string()
+        }
+    };
+
+    ctx.set_active_console(OVERLAY_CONSOLE);
+    ctx.print_color(
+        5,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("Look: {}", description),
+    );
+    if let Some(screen) = world_to_screen(&gs.ecs, cursor) {
+        ctx.set_bg(screen.x, screen.y, RGB::named(rltk::CYAN));
+    }
+    ctx.set_active_console(ASCII_CONSOLE);
+
+    let key = match ctx.key {
+        None => return LookResult::NoResponse { cursor },
+        Some(key) => key,
+    };
+    if key == VirtualKeyCode::Escape || key == VirtualKeyCode::Return {
+        return LookResult::Close;
+    }
+
+    let (dx, dy) = match gs.ecs.fetch::<Settings>().keymap.command(key) {
+        Some(Command::MoveNorth) => (0, -1),
+        Some(Command::MoveSouth) => (0, 1),
+        Some(Command::MoveWest) => (-1, 0),
+        Some(Command::MoveEast) => (1, 0),
+        Some(Command::MoveNorthEast) => (1, -1),
+        Some(Command::MoveNorthWest) => (-1, -1),
+        Some(Command::MoveSouthEast) => (1, 1),
+        Some(Command::MoveSouthWest) => (-1, 1),
+        Some(Command::Look) => return LookResult::Close,
+        _ => (0, 0),
+    };
+    let map = gs.ecs.fetch::<Map>();
+    let moved = Point::new(cursor.x + dx, cursor.y + dy);
+    if map.in_bounds(moved.x, moved.y) && world_to_screen(&gs.ecs, moved).is_some() {
+        LookResult::NoResponse { cursor: moved }
+    } else {
+        LookResult::NoResponse { cursor }
+    }
+}
+
 pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     let runstate = gs.ecs.fetch::<RunState>();
     let save_exists = super::does_save_exist();
@@ -462,7 +707,7 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
         24,
         18,
         31,
-        12,
+        13,
         RGB::named(rltk::WHEAT),
         RGB::named(rltk::BLACK),
     );
@@ -498,6 +743,8 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
         let mut ng_fg = RGB::named(rltk::WHITE);
         let mut sg_fg = RGB::named(rltk::WHITE);
         let mut lg_fg = RGB::named(rltk::WHITE);
+        let mut o_fg = RGB::named(rltk::WHITE);
+        let mut hs_fg = RGB::named(rltk::WHITE);
         let mut q_fg = RGB::named(rltk::WHITE);
 
         // Highlight the currently selected option in magenta
@@ -506,6 +753,8 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
             MainMenuSelection::NewGame => ng_fg = RGB::named(rltk::MAGENTA),
             MainMenuSelection::SaveGame => sg_fg = RGB::named(rltk::MAGENTA),
             MainMenuSelection::LoadGame => lg_fg = RGB::named(rltk::MAGENTA),
+            MainMenuSelection::Options => o_fg = RGB::named(rltk::MAGENTA),
+            MainMenuSelection::HighScores => hs_fg = RGB::named(rltk::MAGENTA),
             MainMenuSelection::Quit => q_fg = RGB::named(rltk::MAGENTA),
         }
 
@@ -520,6 +769,10 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
             ctx.print_color_centered(y, lg_fg, RGB::named(rltk::BLACK), "Load Game");
             y += 1;
         }
+        ctx.print_color_centered(y, o_fg, RGB::named(rltk::BLACK), "Options");
+        y += 1;
+        ctx.print_color_centered(y, hs_fg, RGB::named(rltk::BLACK), "High Scores");
+        y += 1;
         if can_quit {
             ctx.print_color_centered(y, q_fg, RGB::named(rltk::BLACK), "Quit");
             // y += 1;
@@ -544,11 +797,13 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
                         MainMenuSelection::NewGame => newselection = MainMenuSelection::ResumeGame,
                         MainMenuSelection::SaveGame => newselection = MainMenuSelection::NewGame,
                         MainMenuSelection::LoadGame => newselection = MainMenuSelection::SaveGame,
-                        MainMenuSelection::Quit => newselection = MainMenuSelection::LoadGame,
+                        MainMenuSelection::Options => newselection = MainMenuSelection::LoadGame,
+                        MainMenuSelection::HighScores => newselection = MainMenuSelection::Options,
+                        MainMenuSelection::Quit => newselection = MainMenuSelection::HighScores,
                     }
                     // Skip Quit Game option if this would crash game (in wasm)
                     if newselection == MainMenuSelection::Quit && !can_quit {
-                        newselection = MainMenuSelection::LoadGame;
+                        newselection = MainMenuSelection::HighScores;
                     }
                     // When a save file does not exist, skip Load Game option
                     if newselection == MainMenuSelection::LoadGame && !save_exists {
@@ -565,13 +820,15 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
                         MainMenuSelection::ResumeGame => newselection = MainMenuSelection::NewGame,
                         MainMenuSelection::NewGame => newselection = MainMenuSelection::SaveGame,
                         MainMenuSelection::SaveGame => newselection = MainMenuSelection::LoadGame,
-                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::Quit,
+                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::Options,
+                        MainMenuSelection::Options => newselection = MainMenuSelection::HighScores,
+                        MainMenuSelection::HighScores => newselection = MainMenuSelection::Quit,
                         MainMenuSelection::Quit => newselection = MainMenuSelection::ResumeGame,
                     }
 
                     // When a save file does not exist, skip Load Game option
                     if newselection == MainMenuSelection::LoadGame && !save_exists {
-                        newselection = MainMenuSelection::Quit;
+                        newselection = MainMenuSelection::Options;
                     }
 
                     // Skip Quit Game option if this would crash game (in wasm)
@@ -602,22 +859,529 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     }
 }
 
-pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<Equipped>();
-    let entities = gs.ecs.entities();
+#[derive(PartialEq, Copy, Clone)]
+pub enum OptionsSelection {
+    PostEffect,
+    Font,
+    Display,
+    LogLength,
+    AutoPickup,
+    MapgenVisualizer,
+    KeyBindings,
+    Back,
+}
 
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count();
+/// The options in the order they're listed
+const OPTIONS: [OptionsSelection; 8] = [
+    OptionsSelection::PostEffect,
+    OptionsSelection::Font,
+    OptionsSelection::Display,
+    OptionsSelection::LogLength,
+    OptionsSelection::AutoPickup,
+    OptionsSelection::MapgenVisualizer,
+    OptionsSelection::KeyBindings,
+    OptionsSelection::Back,
+];
 
-    let mut y = (25 - (count / 2)) as i32;
+#[derive(PartialEq, Copy, Clone)]
+pub enum OptionsMenuResult {
+    NoSelection { selected: OptionsSelection },
+    KeyBindings,
+    Back,
+}
+
+/// Shows the options, changing and saving them as they are picked
+pub fn options_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    selection: OptionsSelection,
+) -> OptionsMenuResult {
+    let mut settings = gs.ecs.write_resource::<Settings>();
+
+    let assets = gs.ecs.fetch::<RexAssets>();
+    ctx.render_xp_sprite(&assets.menu, 0, 0);
+    ctx.draw_box(
+        20,
+        16,
+        39,
+        16,
+        RGB::named(rltk::WHEAT),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color_centered(
+        18,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Options",
+    );
+    ctx.print_color_centered(
+        19,
+        RGB::named(rltk::GRAY),
+        RGB::named(rltk::BLACK),
+        "Up/Down to pick, Left/Right to change",
+    );
+
+    let post_effect = match settings.post_effect {
+        PostEffect::None => "Off",
+        PostEffect::Scanlines => "Scanlines",
+        PostEffect::Crt => "CRT",
+    };
+    let font = match settings.font {
+        FontChoice::Terminal8x8 => "Terminal 8x8",
+        FontChoice::Vga8x16 => "VGA 8x16",
+    };
+    let display = if settings.fullscreen {
+        "Fullscreen".to_string()
+    } else {
+        format!("Window x{}", settings.window_scale)
+    };
+    let auto_pickup = match settings.auto_pickup {
+        AutoPickup::Off => "Off",
+        AutoPickup::Consumables => "Consumables",
+        AutoPickup::Everything => "Everything",
+    };
+
+    for (i, option) in OPTIONS.iter().enumerate() {
+        let text = match option {
+            OptionsSelection::PostEffect => format!("Effect: {}", post_effect),
+            OptionsSelection::Font => format!("Font: {}", font),
+            OptionsSelection::Display => format!("Display: {}", display),
+            OptionsSelection::LogLength => format!("Message log: {}", settings.log_length),
+            OptionsSelection::AutoPickup => format!("Auto-pickup: {}", auto_pickup),
+            OptionsSelection::MapgenVisualizer => format!(
+                "Mapgen visualizer: {}",
+                if settings.show_mapgen { "On" } else { "Off" }
+            ),
+            OptionsSelection::KeyBindings => "Key Bindings".to_string(),
+            OptionsSelection::Back => "Back".to_string(),
+        };
+        let fg = if *option == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color_centered(21 + i as i32, fg, RGB::named(rltk::BLACK), &text);
+    }
+    ctx.print_color_centered(
+        30,
+        RGB::named(rltk::GRAY),
+        RGB::named(rltk::BLACK),
+        "Font and display apply on restart",
+    );
+
+    let index = OPTIONS.iter().position(|o| *o == selection).unwrap_or(0);
+    let selected = match ctx.key {
+        None => selection,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => return OptionsMenuResult::Back,
+            VirtualKeyCode::Return if selection == OptionsSelection::Back => {
+                return OptionsMenuResult::Back
+            }
+            VirtualKeyCode::Return if selection == OptionsSelection::KeyBindings => {
+                return OptionsMenuResult::KeyBindings
+            }
+            VirtualKeyCode::Up | VirtualKeyCode::K => {
+                OPTIONS[(index + OPTIONS.len() - 1) % OPTIONS.len()]
+            }
+            VirtualKeyCode::Down | VirtualKeyCode::J => OPTIONS[(index + 1) % OPTIONS.len()],
+            VirtualKeyCode::Left
+            | VirtualKeyCode::H
+            | VirtualKeyCode::Right
+            | VirtualKeyCode::L
+            | VirtualKeyCode::Return => {
+                let forward = !matches!(key, VirtualKeyCode::Left | VirtualKeyCode::H);
+                match selection {
+                    OptionsSelection::PostEffect => {
+                        settings.post_effect = match (settings.post_effect, forward) {
+                            (PostEffect::None, true) => PostEffect::Scanlines,
+                            (PostEffect::Scanlines, true) => PostEffect::Crt,
+                            (PostEffect::Crt, true) => PostEffect::None,
+                            (PostEffect::None, false) => PostEffect::Crt,
+                            (PostEffect::Scanlines, false) => PostEffect::None,
+                            (PostEffect::Crt, false) => PostEffect::Scanlines,
+                        }
+                    }
+                    OptionsSelection::Font => {
+                        settings.font = match settings.font {
+                            FontChoice::Terminal8x8 => FontChoice::Vga8x16,
+                            FontChoice::Vga8x16 => FontChoice::Terminal8x8,
+                        }
+                    }
+                    OptionsSelection::Display => {
+                        // Cycles through each window scale, then fullscreen
+                        let steps = MAX_WINDOW_SCALE + 1;
+                        let current = if settings.fullscreen {
+                            MAX_WINDOW_SCALE
+                        } else {
+                            settings.window_scale - 1
+                        };
+                        let next = if forward {
+                            (current + 1) % steps
+                        } else {
+                            (current + steps - 1) % steps
+                        };
+                        settings.fullscreen = next == MAX_WINDOW_SCALE;
+                        if !settings.fullscreen {
+                            settings.window_scale = next + 1;
+                        }
+                    }
+                    OptionsSelection::LogLength => {
+                        let current = LOG_LENGTHS
+                            .iter()
+                            .position(|l| *l == settings.log_length)
+                            .unwrap_or(0);
+                        let next = if forward {
+                            (current + 1) % LOG_LENGTHS.len()
+                        } else {
+                            (current + LOG_LENGTHS.len() - 1) % LOG_LENGTHS.len()
+                        };
+                        settings.log_length = LOG_LENGTHS[next];
+                    }
+                    OptionsSelection::AutoPickup => {
+                        settings.auto_pickup = match (settings.auto_pickup, forward) {
+                            (AutoPickup::Off, true) => AutoPickup::Consumables,
+                            (AutoPickup::Consumables, true) => AutoPickup::Everything,
+                            (AutoPickup::Everything, true) => AutoPickup::Off,
+                            (AutoPickup::Off, false) => AutoPickup::Everything,
+                            (AutoPickup::Consumables, false) => AutoPickup::Off,
+                            (AutoPickup::Everything, false) => AutoPickup::Consumables,
+                        }
+                    }
+                    OptionsSelection::MapgenVisualizer => {
+                        settings.show_mapgen = !settings.show_mapgen
+                    }
+                    OptionsSelection::KeyBindings | OptionsSelection::Back => {}
+                }
+                settings.apply(ctx);
+                save_settings(&settings);
+                selection
+            }
+            _ => selection,
+        },
+    };
+
+    OptionsMenuResult::NoSelection { selected }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum KeyBindingsResult {
+    NoResponse {
+        selection: usize,
+        capturing: bool,
+        conflict: Option<Command>,
+    },
+    Back,
+}
+
+/// Lists every command and its keys. Enter waits for a key to add to the
+/// selected command, refusing keys bound to another command, and Delete
+/// clears its keys
+pub fn key_bindings_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    selection: usize,
+    capturing: bool,
+    conflict: Option<Command>,
+) -> KeyBindingsResult {
+    let mut settings = gs.ecs.write_resource::<Settings>();
+    let height = ALL_COMMANDS.len() as i32 + 6;
+    let top = 25 - height / 2;
+
+    ctx.draw_box(
+        10,
+        top,
+        59,
+        height,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        13,
+        top,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Key Bindings",
+    );
+    ctx.print_color(
+        13,
+        top + height,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Enter: add key  Delete: clear  Escape: back",
+    );
+
+    for (i, command) in ALL_COMMANDS.iter().enumerate() {
+        let y = top + 2 + i as i32;
+        let fg = if i == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        let keys = settings
+            .keymap
+            .keys(*command)
+            .iter()
+            .map(|k| key_name(*k))
+            .collect::<Vec<String>>()
+            .join(", ");
+        ctx.print_color(12, y, fg, RGB::named(rltk::BLACK), command.description());
+        ctx.print_color(30, y, fg, RGB::named(rltk::BLACK), &keys);
+    }
+
+    let status_y = top + height - 2;
+    if capturing {
+        ctx.print_color(
+            12,
+            status_y,
+            RGB::named(rltk::CYAN),
+            RGB::named(rltk::BLACK),
+            "Press a key to bind, or Escape to cancel",
+        );
+    } else if let Some(conflict) = conflict {
+        ctx.print_color(
+            12,
+            status_y,
+            RGB::named(rltk::RED),
+            RGB::named(rltk::BLACK),
+            format!("That key is already bound to {}", conflict.description()),
+        );
+    }
+
+    let key = match ctx.key {
+        None => {
+            return KeyBindingsResult::NoResponse {
+                selection,
+                capturing,
+                conflict,
+            }
+        }
+        Some(key) => key,
+    };
+
+    if capturing {
+        if key == VirtualKeyCode::Escape || !is_bindable(key) {
+            return KeyBindingsResult::NoResponse {
+                selection,
+                capturing: false,
+                conflict: None,
+            };
+        }
+        let conflict = settings.keymap.bind(ALL_COMMANDS[selection], key).err();
+        if conflict.is_none() {
+            save_settings(&settings);
+        }
+        return KeyBindingsResult::NoResponse {
+            selection,
+            capturing: false,
+            conflict,
+        };
+    }
+
+    match key {
+        VirtualKeyCode::Escape => KeyBindingsResult::Back,
+        VirtualKeyCode::Up | VirtualKeyCode::K => KeyBindingsResult::NoResponse {
+            selection: (selection + ALL_COMMANDS.len() - 1) % ALL_COMMANDS.len(),
+            capturing: false,
+            conflict: None,
+        },
+        VirtualKeyCode::Down | VirtualKeyCode::J => KeyBindingsResult::NoResponse {
+            selection: (selection + 1) % ALL_COMMANDS.len(),
+            capturing: false,
+            conflict: None,
+        },
+        VirtualKeyCode::Return => KeyBindingsResult::NoResponse {
+            selection,
+            capturing: true,
+            conflict: None,
+        },
+        VirtualKeyCode::Delete | VirtualKeyCode::Back => {
+            settings.keymap.clear(ALL_COMMANDS[selection]);
+            save_settings(&settings);
+            KeyBindingsResult::NoResponse {
+                selection,
+                capturing: false,
+                conflict: None,
+            }
+        }
+        _ => KeyBindingsResult::NoResponse {
+            selection,
+            capturing,
+            conflict,
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum HelpResult {
+    NoResponse { page: usize },
+    Close,
+}
+
+const HELP_PAGES: usize = 2;
+
+/// Shows the command reference, built from the current key bindings, and a
+/// legend of what's drawn on the map
+pub fn help_screen(gs: &mut State, ctx: &mut Rltk, page: usize) -> HelpResult {
+    let settings = gs.ecs.fetch::<Settings>();
+    ctx.draw_box(
+        5,
+        2,
+        69,
+        40,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        8,
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("Help ({}/{})", page + 1, HELP_PAGES),
+    );
+    ctx.print_color(
+        8,
+        42,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Left/Right: change page  Escape: close",
+    );
+
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let heading = RGB::named(rltk::CYAN);
+    let mut y = 4;
+    if page == 0 {
+        ctx.print_color(7, y, heading, black, "Commands");
+        y += 2;
+        for command in ALL_COMMANDS.iter() {
+            let keys = settings
+                .keymap
+                .keys(*command)
+                .iter()
+                .map(|k| key_name(*k))
+                .collect::<Vec<String>>();
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys.join(", ")
+            };
+            ctx.print_color(7, y, white, black, command.description());
+            ctx.print_color(28, y, white, black, &keys);
+            y += 1;
+        }
+
+        y += 1;
+        ctx.print_color(7, y, heading, black, "Menus");
+        y += 2;
+        let menu_keys = [
+            ("Main menu", "Escape"),
+            ("Pick a menu item", "Up/Down and Return"),
+            ("Pick from a list", "Its letter, Escape to cancel"),
+            ("Aim a spell", "Left click on a blue tile"),
+            ("Attack a shopkeeper", "Shift and a move key"),
+            ("Rebind keys", "Main menu, Options, Key Bindings"),
+        ];
+        for (action, keys) in menu_keys.iter() {
+            ctx.print_color(7, y, white, black, action);
+            ctx.print_color(28, y, white, black, keys);
+            y += 1;
+        }
+    } else {
+        ctx.print_color(7, y, heading, black, "Map legend");
+        y += 2;
+        for (glyph, fg, bg, description) in map_legend().iter() {
+            ctx.set(8, y, *fg, *bg, *glyph);
+            ctx.print_color(11, y, white, black, description);
+            y += 1;
+        }
+
+        y += 1;
+        ctx.print_color(7, y, heading, black, "Colors");
+        y += 2;
+        let notes = [
+            "Grey tiles are remembered, but out of sight",
+            "Dim tiles are in darkness; bring a light",
+            "Hover the mouse over something to name it",
+            "(asleep) and (unaware) monsters can be snuck up on",
+        ];
+        for note in notes.iter() {
+            ctx.print_color(7, y, white, black, note);
+            y += 1;
+        }
+    }
+
+    match ctx.key {
+        None => HelpResult::NoResponse { page },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => HelpResult::Close,
+            VirtualKeyCode::Left | VirtualKeyCode::H => HelpResult::NoResponse {
+                page: (page + HELP_PAGES - 1) % HELP_PAGES,
+            },
+            VirtualKeyCode::Right | VirtualKeyCode::L | VirtualKeyCode::Tab => {
+                HelpResult::NoResponse {
+                    page: (page + 1) % HELP_PAGES,
+                }
+            }
+            _ => HelpResult::NoResponse { page },
+        },
+    }
+}
+
+/// What the player wears in each slot, in paper doll order
+fn worn_equipment(ecs: &World) -> Vec<(EquipmentSlot, Option<Entity>)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+    EquipmentSlot::ALL
+        .iter()
+        .map(|slot| {
+            let item = (&entities, &equipped)
+                .join()
+                .find(|(_, worn)| worn.owner == *player_entity && worn.slot == *slot)
+                .map(|(item, _)| item);
+            (*slot, item)
+        })
+        .collect()
+}
+
+/// Draws what the player wears slot by slot from `x`, `y`, with a letter to
+/// pick each slot by if `lettered`
+fn draw_paper_doll(ecs: &World, ctx: &mut Rltk, x: i32, y: i32, lettered: bool) {
+    let white = RGB::named(rltk::WHITE);
+    let grey = RGB::named(rltk::GREY);
+    let black = RGB::named(rltk::BLACK);
+    for (i, (slot, item)) in worn_equipment(ecs).iter().enumerate() {
+        let y = y + i as i32;
+        let mut label_x = x;
+        if lettered {
+            ctx.set(x, y, white, black, rltk::to_cp437('('));
+            ctx.set(
+                x + 1,
+                y,
+                RGB::named(rltk::YELLOW),
+                black,
+                97 + i as rltk::FontCharType,
+            );
+            ctx.set(x + 2, y, white, black, rltk::to_cp437(')'));
+            label_x += 4;
+        }
+        ctx.print_color(label_x, y, grey, black, slot.label());
+        match item {
+            Some(item) => ctx.print_color(label_x + 12, y, white, black, describe_item(ecs, *item)),
+            None => ctx.print_color(label_x + 12, y, grey, black, "-"),
+        }
+    }
+}
+
+pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let slots = worn_equipment(&gs.ecs);
+    let count = slots.len();
+
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(
         15,
         y - 2,
-        31,
+        item_menu_width(&gs.ecs) + 12,
         (count + 3) as i32,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
@@ -636,13 +1400,282 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
         RGB::named(rltk::BLACK),
         "ESCAPE to cancel",
     );
+    draw_paper_doll(&gs.ecs, ctx, 17, y, true);
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    let mut j = 0;
-    for (entity, _pack, name) in (&entities, &backpack, &names)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    if let (_, Some(item)) = slots[selection as usize] {
+                        return (ItemMenuResult::Selected, Some(item));
+                    }
+                }
+                (ItemMenuResult::NoResponse, None)
+            }
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CharacterSheetResult {
+    NoResponse,
+    Close,
+}
+
+/// The player's stats, with what their equipment adds, and everything they
+/// wear
+pub fn character_sheet(gs: &mut State, ctx: &mut Rltk) -> CharacterSheetResult {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let heading = RGB::named(rltk::CYAN);
+    ctx.draw_box(10, 8, item_menu_width(&gs.ecs) + 10, 26, white, black);
+    ctx.print_color(13, 8, RGB::named(rltk::YELLOW), black, "Character");
+    ctx.print_color(13, 34, RGB::named(rltk::YELLOW), black, "ESCAPE to close");
+
+    {
+        let player_entity = *gs.ecs.fetch::<Entity>();
+        let equipped = gs.ecs.read_storage::<Equipped>();
+        let worn_by_player =
+            |item: Entity| matches!(equipped.get(item), Some(worn) if worn.owner == player_entity);
+        // An off hand weapon strikes separately, so only the main hand counts
+        let power_bonus: i32 = (
+            &gs.ecs.entities(),
+            &gs.ecs.read_storage::<MeleePowerBonus>(),
+        )
+            .join()
+            .filter(|(item, _)| {
+                worn_by_player(*item)
+                    && !matches!(equipped.get(*item), Some(worn) if worn.slot == EquipmentSlot::Shield)
+            })
+            .map(|(_, bonus)| bonus.power)
+            .sum();
+        let defense_bonus: i32 = (&gs.ecs.entities(), &gs.ecs.read_storage::<DefenseBonus>())
+            .join()
+            .filter(|(item, _)| worn_by_player(*item))
+            .map(|(_, bonus)| bonus.defense)
+            .sum();
+
+        let mut y = 10;
+        ctx.print_color(12, y, heading, black, "Stats");
+        y += 2;
+        if let Some(stats) = gs.ecs.read_storage::<CombatStats>().get(player_entity) {
+            ctx.print_color(
+                12,
+                y,
+                white,
+                black,
+                format!("Health:   {}/{}", stats.hp, stats.max_hp),
+            );
+            ctx.print_color(
+                12,
+                y + 1,
+                white,
+                black,
+                format!(
+                    "Power:    {} ({:+})",
+                    stats.power + power_bonus,
+                    power_bonus
+                ),
+            );
+            ctx.print_color(
+                12,
+                y + 2,
+                white,
+                black,
+                format!(
+                    "Defense:  {} ({:+})",
+                    stats.defense + defense_bonus,
+                    defense_bonus
+                ),
+            );
+            y += 3;
+        }
+        if let Some(clock) = gs.ecs.read_storage::<HungerClock>().get(player_entity) {
+            ctx.print_color(12, y, white, black, format!("Hunger:   {:?}", clock.state));
+            y += 1;
+        }
+        if let Some(encumbrance) = gs.ecs.read_storage::<Encumbrance>().get(player_entity) {
+            ctx.print_color(
+                12,
+                y,
+                white,
+                black,
+                format!(
+                    "Load:     {:.1}/{:.0} lbs ({:?})",
+                    encumbrance.load, encumbrance.capacity, encumbrance.level
+                ),
+            );
+            y += 1;
+        }
+        if let Some(purse) = gs.ecs.read_storage::<Purse>().get(player_entity) {
+            ctx.print_color(12, y, white, black, format!("Gold:     {}", purse.gold));
+        }
+    }
+
+    ctx.print_color(12, 20, heading, black, "Equipment");
+    draw_paper_doll(&gs.ecs, ctx, 12, 22, false);
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => CharacterSheetResult::Close,
+        _ => CharacterSheetResult::NoResponse,
+    }
+}
+
+/// An rltk named colour
+type Color = (u8, u8, u8);
+
+/// What the help screen's legend shows for things the spawner makes: what
+/// the spawner calls each, its glyph and colour, and what it means. Potions
+/// and scrolls change colour each game, so they're shown in white
+const LEGEND_ENTITIES: [(&str, char, Color, &str); 17] = [
+    ("Player", '@', rltk::YELLOW, "You"),
+    ("Goblin", 'g', rltk::RED, "Goblin (letters are monsters)"),
+    ("Rat", 'r', rltk::BURLYWOOD, "Critter, scared of you"),
+    (
+        "Shopkeeper",
+        '@',
+        rltk::LIGHT_GREEN,
+        "Shopkeeper, bump to trade",
+    ),
+    (
+        "Health Potion",
+        '¡',
+        rltk::WHITE,
+        "Potion (colors change each game)",
+    ),
+    (
+        "Magic Missile Scroll",
+        ')',
+        rltk::WHITE,
+        "Scroll (colors change each game)",
+    ),
+    ("Rations", '%', rltk::GREEN, "Food"),
+    ("Dagger", '/', rltk::CYAN, "Weapon"),
+    ("Shield", '(', rltk::CYAN, "Shield"),
+    ("Leather Armor", '[', rltk::CYAN, "Armor"),
+    ("Ring of Regeneration", '=', rltk::MAGENTA, "Ring"),
+    ("Amulet of Sustenance", '"', rltk::MAGENTA, "Amulet"),
+    ("Healing Herb", '♣', rltk::LIGHT_GREEN, "Herb, for crafting"),
+    ("Whetstone", '*', rltk::GRAY, "Whetstone"),
+    ("Gold", '$', rltk::GOLD, "Gold"),
+    ("Bear Trap", '^', rltk::RED, "Trap"),
+    ("Brazier", '☼', rltk::ORANGE, "Light source"),
+];
+
+/// A legend line: glyph, foreground, background and what it means
+type LegendEntry = (rltk::FontCharType, RGB, RGB, &'static str);
+
+/// The help screen's map legend. Tiles are shown as `tile_appearance` draws
+/// them
+fn map_legend() -> Vec<LegendEntry> {
+    let black = RGB::named(rltk::BLACK);
+    let entity = |(_, glyph, fg, description): &(&str, char, Color, &'static str)| {
+        (rltk::to_cp437(*glyph), RGB::named(*fg), black, *description)
+    };
+    // You come first, then the map itself, then what's on it
+    let mut entries = vec![entity(&LEGEND_ENTITIES[0])];
+
+    for (tile, description) in [
+        (TileType::Floor, "Floor"),
+        (TileType::Wall, "Wall"),
+        (TileType::DownStairs, "Stairs down"),
+    ]
+    .iter()
     {
+        let (glyph, fg) = tile_appearance(*tile);
+        entries.push((glyph, fg, black, *description));
+    }
+    let (floor, floor_fg) = tile_appearance(TileType::Floor);
+    entries.push((floor, floor_fg, bloodstain_color(), "Bloodstain"));
+
+    entries.extend(LEGEND_ENTITIES[1..].iter().map(entity));
+    entries
+}
+
+/// Whether the shop menu lists the shopkeeper's wares or the player's
+#[derive(PartialEq, Copy, Clone)]
+pub enum ShopMode {
+    Buy,
+    Sell,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ShopResult {
+    NoResponse,
+    SwitchMode,
+    Buy(Entity),
+    Sell(Entity),
+    Close,
+}
+
+/// Lists what `vendor` sells or what the player could sell them, with prices
+pub fn shop_menu(gs: &mut State, ctx: &mut Rltk, vendor: Entity, mode: ShopMode) -> ShopResult {
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    let owner = match mode {
+        ShopMode::Buy => vendor,
+        ShopMode::Sell => player_entity,
+    };
+    let wares: Vec<(Entity, String, Option<i32>)> = {
+        let backpack = gs.ecs.read_storage::<InBackpack>();
+        let entities = gs.ecs.entities();
+        (&entities, &backpack)
+            .join()
+            .filter(|(_, pack)| pack.owner == owner)
+            .map(|(item, _)| {
+                let price = match mode {
+                    ShopMode::Buy => buy_price(&gs.ecs, item),
+                    ShopMode::Sell => sell_price(&gs.ecs, item),
+                };
+                (item, describe_item(&gs.ecs, item), price)
+            })
+            .collect()
+    };
+    let gold = gs
+        .ecs
+        .read_storage::<Purse>()
+        .get(player_entity)
+        .map_or(0, |p| p.gold);
+    let count = wares.len();
+    let width = wares
+        .iter()
+        .map(|(_, name, _)| name.len() as i32 + 17)
+        .fold(40, i32::max);
+
+    let top = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        top - 2,
+        width,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        top - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        match mode {
+            ShopMode::Buy => format!("Buy Which Item? ({} gold)", gold),
+            ShopMode::Sell => format!("Sell Which Item? ({} gold)", gold),
+        },
+    );
+    ctx.print_color(
+        18,
+        top + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        match mode {
+            ShopMode::Buy => "TAB to sell, ESCAPE to leave",
+            ShopMode::Sell => "TAB to buy, ESCAPE to leave",
+        },
+    );
+
+    for (j, (_, name, price)) in wares.iter().enumerate() {
+        let y = top + j as i32;
         ctx.set(
             17,
             y,
@@ -664,59 +1697,293 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
             RGB::named(rltk::BLACK),
             rltk::to_cp437(')'),
         );
-
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
-        y += 1;
-        j += 1;
+        ctx.print(21, y, name);
+        let affordable = mode == ShopMode::Sell || matches!(price, Some(p) if *p <= gold);
+        let price = match price {
+            Some(price) => format!("{:>5} gold", price),
+            None => "         -".to_string(),
+        };
+        ctx.print_color(
+            15 + width - 11,
+            y,
+            if affordable {
+                RGB::named(rltk::GOLD)
+            } else {
+                RGB::named(rltk::GREY)
+            },
+            RGB::named(rltk::BLACK),
+            price,
+        );
     }
 
     match ctx.key {
-        None => (ItemMenuResult::NoResponse, None),
+        None => ShopResult::NoResponse,
         Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+            VirtualKeyCode::Escape => ShopResult::Close,
+            VirtualKeyCode::Tab => ShopResult::SwitchMode,
             _ => {
                 let selection = rltk::letter_to_option(key);
                 if selection > -1 && selection < count as i32 {
-                    return (
-                        ItemMenuResult::Selected,
-                        Some(equippable[selection as usize]),
-                    );
+                    let item = wares[selection as usize].0;
+                    return match mode {
+                        ShopMode::Buy => ShopResult::Buy(item),
+                        ShopMode::Sell => ShopResult::Sell(item),
+                    };
                 }
-                (ItemMenuResult::NoResponse, None)
+                ShopResult::NoResponse
             }
         },
     }
 }
 
-#[derive(PartialEq, Copy, Clone)]
-pub enum GameOverResult {
-    NoSelection,
-    QuitToMenu,
+#[derive(Copy, Clone)]
+pub enum CraftingResult {
+    NoResponse,
+    Craft(Craft),
+    Close,
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
-    ctx.print_color_centered(
+/// Lists what the player can make from what's in their backpack
+pub fn crafting_menu(gs: &mut State, ctx: &mut Rltk) -> CraftingResult {
+    let crafts = available_crafts(&gs.ecs);
+    let labels: Vec<String> = crafts
+        .iter()
+        .map(|craft| craft_label(&gs.ecs, craft))
+        .collect();
+    let count = labels.len();
+    let width = labels
+        .iter()
+        .map(|label| label.len() as i32 + 9)
+        .fold(31, i32::max);
+
+    let top = (25 - (count / 2)) as i32;
+    ctx.draw_box(
         15,
+        top - 2,
+        width,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        top - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Your journey has ended!",
+        "Craft What?",
     );
-    ctx.print_color_centered(
-        17,
+    ctx.print_color(
+        18,
+        top + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+    if count == 0 {
+        ctx.print(18, top, "You have nothing to craft with.");
+    }
+
+    for (j, label) in labels.iter().enumerate() {
+        let y = top + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print(21, y, label);
+    }
+
+    match ctx.key {
+        None => CraftingResult::NoResponse,
+        Some(VirtualKeyCode::Escape) => CraftingResult::Close,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < count as i32 {
+                return CraftingResult::Craft(crafts[selection as usize]);
+            }
+            CraftingResult::NoResponse
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum HighScoresResult {
+    NoResponse {
+        sort: HighScoreSort,
+        selection: usize,
+    },
+    Back,
+}
+
+/// How many runs fit in the high score table at once
+const HIGH_SCORE_ROWS: usize = 32;
+
+/// Lists past runs, sorted by `sort`, with the seed and morgue of the
+/// selected one
+pub fn high_scores(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    sort: HighScoreSort,
+    selection: usize,
+) -> HighScoresResult {
+    let history = gs.ecs.fetch::<RunHistory>();
+    let runs = history.sorted(sort);
+
+    ctx.draw_box(
+        5,
+        2,
+        69,
+        40,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
-        "One day, we'll tell you all about how you did.",
     );
+    ctx.print_color(
+        8,
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("High Scores (by {})", sort.name()),
+    );
+    ctx.print_color(
+        8,
+        42,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Up/Down: select  Tab: sort  Escape: back",
+    );
+
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let heading = RGB::named(rltk::CYAN);
+    let columns = [7, 11, 18, 24, 30, 37, 49];
+    let titles = ["#", "Score", "Depth", "Kills", "Turns", "Date", "Killed by"];
+    for (x, title) in columns.iter().zip(titles.iter()) {
+        ctx.print_color(*x, 4, heading, black, title);
+    }
+
+    if runs.is_empty() {
+        ctx.print_color(7, 6, white, black, "No runs finished yet.");
+    }
+
+    // Scroll so the selected run is always in view
+    let first = (selection + 1).saturating_sub(HIGH_SCORE_ROWS);
+    for (i, run) in runs.iter().enumerate().skip(first).take(HIGH_SCORE_ROWS) {
+        let y = 6 + (i - first) as i32;
+        let fg = if i == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            white
+        };
+        let cause: String = run.cause_of_death.chars().take(24).collect();
+        let cells = [
+            format!("{}", i + 1),
+            format!("{}", run.score),
+            format!("{}", run.depth),
+            format!("{}", run.kills),
+            format!("{}", run.turns),
+            format_date(run.date),
+            cause,
+        ];
+        for (x, cell) in columns.iter().zip(cells.iter()) {
+            ctx.print_color(*x, y, fg, black, cell);
+        }
+    }
+
+    if let Some(run) = runs.get(selection) {
+        ctx.print_color(7, 39, white, black, format!("Seed: {}", run.seed));
+        let morgue = match &run.morgue {
+            Some(file) => format!("Morgue: {}", file),
+            None => "Morgue: not saved".to_string(),
+        };
+        ctx.print_color(7, 40, white, black, morgue);
+    }
+
+    match ctx.key {
+        None => HighScoresResult::NoResponse { sort, selection },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => HighScoresResult::Back,
+            VirtualKeyCode::Tab | VirtualKeyCode::Right => HighScoresResult::NoResponse {
+                sort: sort.next(),
+                selection: 0,
+            },
+            VirtualKeyCode::Up | VirtualKeyCode::K => HighScoresResult::NoResponse {
+                sort,
+                selection: selection.saturating_sub(1),
+            },
+            VirtualKeyCode::Down | VirtualKeyCode::J => HighScoresResult::NoResponse {
+                sort,
+                selection: usize::min(selection + 1, runs.len().saturating_sub(1)),
+            },
+            _ => HighScoresResult::NoResponse { sort, selection },
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum GameOverResult {
+    NoSelection,
+    QuitToMenu,
+}
+
+pub fn game_over(gs: &mut State, ctx: &mut Rltk) -> GameOverResult {
     ctx.print_color_centered(
-        18,
-        RGB::named(rltk::WHITE),
+        5,
+        RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "That day, sadly, is not in this chapter..",
+        "Your journey has ended!",
     );
 
+    let mut y = 7;
+    if let Some(morgue) = gs.ecs.try_fetch::<Morgue>() {
+        // Leave room for the file name and prompt at the bottom
+        for line in morgue.summary.iter().take(36) {
+            ctx.print_color(
+                20,
+                y,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                line,
+            );
+            y += 1;
+        }
+        if let Some(file) = &morgue.file {
+            y += 1;
+            ctx.print_color_centered(
+                y,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                format!("Full morgue written to {}", file),
+            );
+        }
+        if let Some(file) = &morgue.stats_file {
+            y += 1;
+            ctx.print_color_centered(
+                y,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                format!("Statistics exported to {}", file),
+            );
+        }
+    }
+
     ctx.print_color_centered(
-        20,
+        y + 2,
         RGB::named(rltk::MAGENTA),
         RGB::named(rltk::BLACK),
         "Press any key to return to the menu.",
@@ -727,3 +1994,100 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         Some(_) => GameOverResult::QuitToMenu,
     }
 }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum MapgenResult {
+    NoResponse,
+    TogglePause,
+    StepForward,
+    StepBack,
+    SpeedUp,
+    SlowDown,
+    SkipToEnd,
+    Regenerate,
+    Continue,
+}
+
+/// Draws the mapgen visualizer's overlay, naming the builder and the step
+/// being shown, and reads the playback controls
+pub fn mapgen_visualizer(gs: &State, ctx: &mut Rltk) -> MapgenResult {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    ctx.draw_box(0, 43, 79, 6, white, black);
+
+    let steps = gs.mapgen_history.len();
+    if let Some(snapshot) = gs.mapgen_history.get(gs.mapgen_index) {
+        ctx.print_color(
+            2,
+            44,
+            RGB::named(rltk::YELLOW),
+            black,
+            format!(
+                "{}: step {}/{}{}",
+                gs.mapgen_builder,
+                gs.mapgen_index + 1,
+                steps,
+                if gs.mapgen_paused { " (paused)" } else { "" }
+            ),
+        );
+        ctx.print_color(2, 45, white, black, &snapshot.description);
+    }
+    ctx.print_color(
+        2,
+        47,
+        RGB::named(rltk::GRAY),
+        black,
+        format!(
+            "Space: pause  Left/Right: step  Up/Down: speed ({}ms)",
+            gs.mapgen_delay as i32
+        ),
+    );
+    ctx.print_color(
+        2,
+        48,
+        RGB::named(rltk::GRAY),
+        black,
+        "End: skip to end  R: regenerate  Enter: continue",
+    );
+
+    match ctx.key {
+        None => MapgenResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::Space => MapgenResult::TogglePause,
+            VirtualKeyCode::Right => MapgenResult::StepForward,
+            VirtualKeyCode::Left => MapgenResult::StepBack,
+            VirtualKeyCode::Up => MapgenResult::SpeedUp,
+            VirtualKeyCode::Down => MapgenResult::SlowDown,
+            VirtualKeyCode::End => MapgenResult::SkipToEnd,
+            VirtualKeyCode::R => MapgenResult::Regenerate,
+            VirtualKeyCode::Return | VirtualKeyCode::Escape => MapgenResult::Continue,
+            _ => MapgenResult::NoResponse,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LEGEND_ENTITIES;
+    use crate::test_support::test_world;
+    use crate::{spawner, Renderable};
+    use specs::prelude::*;
+
+    #[test]
+    fn legend_matches_what_the_spawner_makes() {
+        let mut ecs = test_world();
+        for (name, glyph, fg, _) in LEGEND_ENTITIES.iter() {
+            let entity = if *name == "Player" {
+                *ecs.fetch::<Entity>()
+            } else {
+                spawner::spawn_named(&mut ecs, name, 2, 2).expect(name)
+            };
+            let renderables = ecs.read_storage::<Renderable>();
+            let render = renderables.get(entity).expect(name);
+            assert_eq!(render.glyph, rltk::to_cp437(*glyph), "{}", name);
+            if !name.contains("Potion") && !name.contains("Scroll") {
+                assert_eq!(render.fg, rltk::RGB::named(*fg), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::{load_json, save_json, Map, Morgue, RunStats};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+/// What the run history is saved under
+const HISTORY_KEY: &str = "history";
+
+/// A finished run, as kept in the run history
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub score: i32,
+    pub seed: u64,
+    /// When the run ended, in seconds since the Unix epoch
+    pub date: u64,
+    pub depth: i32,
+    pub kills: i32,
+    pub turns: i32,
+    pub cause_of_death: String,
+    /// Where the run's morgue was written, if it could be
+    pub morgue: Option<String>,
+}
+
+/// Every finished run, kept between games in a history file
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+/// Orders the high score table can be sorted in, best first
+#[derive(PartialEq, Copy, Clone)]
+pub enum HighScoreSort {
+    Score,
+    Depth,
+    Kills,
+    Turns,
+    Date,
+}
+
+impl HighScoreSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            HighScoreSort::Score => "score",
+            HighScoreSort::Depth => "depth",
+            HighScoreSort::Kills => "kills",
+            HighScoreSort::Turns => "turns",
+            HighScoreSort::Date => "date",
+        }
+    }
+
+    /// The sort after this one, wrapping around
+    pub fn next(self) -> HighScoreSort {
+        match self {
+            HighScoreSort::Score => HighScoreSort::Depth,
+            HighScoreSort::Depth => HighScoreSort::Kills,
+            HighScoreSort::Kills => HighScoreSort::Turns,
+            HighScoreSort::Turns => HighScoreSort::Date,
+            HighScoreSort::Date => HighScoreSort::Score,
+        }
+    }
+}
+
+impl RunHistory {
+    /// Returns the runs ordered by `sort`, best or most recent first
+    pub fn sorted(&self, sort: HighScoreSort) -> Vec<&RunRecord> {
+        let mut runs: Vec<&RunRecord> = self.runs.iter().collect();
+        runs.sort_by_key(|run| {
+            std::cmp::Reverse(match sort {
+                HighScoreSort::Score => (run.score as i64, run.date),
+                HighScoreSort::Depth => (run.depth as i64, run.date),
+                HighScoreSort::Kills => (run.kills as i64, run.date),
+                HighScoreSort::Turns => (run.turns as i64, run.date),
+                HighScoreSort::Date => (run.date as i64, run.date),
+            })
+        });
+        runs
+    }
+}
+
+/// Scores a run: going deeper counts most, then kills, then staying alive
+pub fn score(depth: i32, kills: i32, turns: i32) -> i32 {
+    depth * 100 + kills * 10 + turns / 10
+}
+
+/// Adds the run that just ended to the history and saves it. Expects the
+/// `Morgue` to have been written already
+pub fn record_run(ecs: &mut World) {
+    let record = {
+        let stats = ecs.fetch::<RunStats>();
+        let map = ecs.fetch::<Map>();
+        let kills: i32 = stats.kills.values().sum();
+        let morgue = match ecs.try_fetch::<Morgue>() {
+            Some(morgue) => morgue.file.clone(),
+            None => None,
+        };
+
+        RunRecord {
+            score: score(map.depth, kills, stats.turns),
+            seed: stats.seed,
+            date: now(),
+            depth: map.depth,
+            kills,
+            turns: stats.turns,
+            cause_of_death: match &stats.cause_of_death {
+                Some(cause) => cause.clone(),
+                None => "something unknown".to_string(),
+            },
+            morgue,
+        }
+    };
+
+    let mut history = ecs.write_resource::<RunHistory>();
+    history.runs.push(record);
+    save_history(&history);
+}
+
+/// Formats seconds since the Unix epoch as a year-month-day date
+pub fn format_date(secs: u64) -> String {
+    // Days to civil date, after Howard Hinnant's algorithm
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Loads the run history, starting a new one if there isn't a usable one
+pub fn load_history() -> RunHistory {
+    load_json(HISTORY_KEY).unwrap_or_default()
+}
+
+/// Saves the run history for next time. If it can't be saved the run is
+/// still listed until the game is closed
+fn save_history(history: &RunHistory) {
+    if let Err(e) = save_json(HISTORY_KEY, history) {
+        rltk::console::log(format!("Unable to save the run history: {}", e));
+    }
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{HungerClock, RunState, HungerState, SufferDamage, gamelog::GameLog};
+use super::{HungerClock, RunState, HungerState, SufferDamage, DamageSource, RunStats, Encumbrance, EncumbranceLevel, Equipped, Sustenance, gamelog::GameLog};
 
 pub struct HungerSystem {}
 
@@ -11,11 +11,15 @@ impl<'a> System<'a> for HungerSystem {
                         ReadExpect<'a, Entity>, // The player
                         ReadExpect<'a, RunState>,
                         WriteStorage<'a, SufferDamage>,
-                        WriteExpect<'a, GameLog>
+                        WriteExpect<'a, GameLog>,
+                        WriteExpect<'a, RunStats>,
+                        ReadStorage<'a, Encumbrance>,
+                        ReadStorage<'a, Equipped>,
+                        ReadStorage<'a, Sustenance>
                       );
 
     fn run(&mut self, data : Self::SystemData) {
-        let (entities, mut hunger_clock, player_entity, runstate, mut inflict_damage, mut log) = data;
+        let (entities, mut hunger_clock, player_entity, runstate, mut inflict_damage, mut log, mut run_stats, encumbrance, equipped, sustenance) = data;
 
         for (entity, mut clock) in (&entities, &mut hunger_clock).join() {
             let mut proceed = false;
@@ -34,9 +38,23 @@ impl<'a> System<'a> for HungerSystem {
                 _ => proceed = false
             }
 
+            // Anything sustaining them lets every other turn go by for free
+            let sustained = (&equipped, &sustenance).join().any(|(worn, _)| worn.owner == entity);
+            if sustained && run_stats.turns % 2 == 1 {
+                proceed = false;
+            }
+
             if proceed {
                 clock.duration -= 1;
+
+                // Lugging a heavy load works up an appetite
+                match encumbrance.get(entity).map(|e| e.level) {
+                    Some(EncumbranceLevel::Burdened) if run_stats.turns % 2 == 0 => clock.duration -= 1,
+                    Some(EncumbranceLevel::Stressed) => clock.duration -= 1,
+                    _ => {}
+                }
                 if clock.duration < 1 {
+                    let previous_state = clock.state;
                     match clock.state {
                         HungerState::WellFed => {
                             clock.state = HungerState::Normal;
@@ -64,9 +82,12 @@ impl<'a> System<'a> for HungerSystem {
                             if entity == *player_entity {
                                 log.entries.push("Your hunger pangs are getting painful! You suffer 1 hp damage.".to_string());
                             }
-                            SufferDamage::new_damage(&mut inflict_damage, entity, 1);  
+                            SufferDamage::new_damage(&mut inflict_damage, entity, 1, DamageSource::new("starvation", false));  
                         }
                     }
+                    if entity == *player_entity && clock.state != previous_state {
+                        *run_stats.hunger_states.entry(format!("{:?}", clock.state)).or_insert(0) += 1;
+                    }
                 }
             }
         }
@@ -0,0 +1,268 @@
+use super::{Charges, Cursed, Enchantment, Gold, Name, Sharpened, Stackable};
+use rltk::{RandomNumberGenerator, RGB};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Potions and scrolls, which look the same until the player learns what they
+/// are
+const POTIONS: [&str; 1] = ["Health Potion"];
+const SCROLLS: [&str; 6] = [
+    "Magic Missile Scroll",
+    "Fireball Scroll",
+    "Confusion Scroll",
+    "Scroll of Magic Mapping",
+    "Identify Scroll",
+    "Remove Curse Scroll",
+];
+
+/// What an unidentified potion can look like
+const POTION_LOOKS: [(&str, (u8, u8, u8)); 8] = [
+    ("Murky", rltk::BROWN1),
+    ("Bubbling", rltk::GREEN),
+    ("Smoky", rltk::GREY),
+    ("Golden", rltk::GOLD),
+    ("Violet", rltk::VIOLET),
+    ("Crimson", rltk::CRIMSON),
+    ("Milky", rltk::WHITE),
+    ("Glowing", rltk::MAGENTA),
+];
+
+// Every potion needs a look of its own, or two would pass for each other
+const _: () = assert!(POTION_LOOKS.len() >= POTIONS.len());
+
+/// Scroll labels are made up from these
+const SYLLABLES: [&str; 16] = [
+    "zel", "go", "mer", "ka", "bra", "xu", "lor", "vin", "ta", "fu", "nix", "o", "dre", "ash",
+    "pli", "um",
+];
+const SCROLL_COLORS: [(u8, u8, u8); 7] = [
+    rltk::CYAN,
+    rltk::ORANGE,
+    rltk::PINK,
+    rltk::CYAN3,
+    rltk::YELLOW,
+    rltk::LIGHT_BLUE,
+    rltk::TAN,
+];
+
+/// How an unidentified item is shown
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Disguise {
+    pub name: String,
+    pub color: RGB,
+}
+
+/// The disguises the potions and scrolls wear this run and which of them the
+/// player has seen through. Saved with the game
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ItemIdentities {
+    /// Each disguised item's disguise, by its real name
+    pub disguises: BTreeMap<String, Disguise>,
+    /// Real names of the items the player has identified
+    pub known: BTreeSet<String>,
+}
+
+impl ItemIdentities {
+    /// Hands out this run's disguises, chosen from `seed`
+    pub fn new(seed: u64) -> ItemIdentities {
+        // Kept apart from the game's dice so levels come out the same
+        let mut rng = RandomNumberGenerator::seeded(seed ^ 0x1de7_1f1e);
+        let mut disguises = BTreeMap::new();
+
+        let mut looks = POTION_LOOKS.to_vec();
+        for potion in POTIONS.iter() {
+            let (look, color) = looks.remove(rng.range(0, looks.len() as i32) as usize);
+            disguises.insert(
+                potion.to_string(),
+                Disguise {
+                    name: format!("{} Potion", look),
+                    color: RGB::named(color),
+                },
+            );
+        }
+
+        // Labels tell scrolls apart, so once every color has been used they
+        // can start coming round again
+        let mut colors = SCROLL_COLORS.to_vec();
+        for scroll in SCROLLS.iter() {
+            if colors.is_empty() {
+                colors = SCROLL_COLORS.to_vec();
+            }
+            let color = colors.remove(rng.range(0, colors.len() as i32) as usize);
+            let label = loop {
+                let words: Vec<String> = (0..rng.range(1, 3))
+                    .map(|_| {
+                        (0..rng.range(2, 4))
+                            .map(|_| SYLLABLES[rng.range(0, SYLLABLES.len() as i32) as usize])
+                            .collect::<String>()
+                            .to_uppercase()
+                    })
+                    .collect();
+                let label = format!("Scroll labelled {}", words.join(" "));
+                if disguises.values().all(|d: &Disguise| d.name != label) {
+                    break label;
+                }
+            };
+            disguises.insert(
+                scroll.to_string(),
+                Disguise {
+                    name: label,
+                    color: RGB::named(color),
+                },
+            );
+        }
+
+        ItemIdentities {
+            disguises,
+            known: BTreeSet::new(),
+        }
+    }
+
+    /// What the player knows the item called `name` as
+    pub fn display_name(&self, name: &str) -> String {
+        match self.disguises.get(name) {
+            Some(disguise) if !self.known.contains(name) => disguise.name.clone(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// The full name the player knows an item by, with its enchantment and
+    /// any curse once they've been found out
+    pub fn item_name(&self, name: &str, enchantment: Option<&Enchantment>, cursed: bool) -> String {
+        let name = self.display_name(name);
+        match enchantment {
+            Some(enchantment) if enchantment.known => format!(
+                "{:+} {}{}",
+                enchantment.bonus,
+                name,
+                if cursed { " (cursed)" } else { "" }
+            ),
+            _ => name,
+        }
+    }
+
+    /// The colour items called `name` are drawn in, if they wear a disguise
+    pub fn color(&self, name: &str) -> Option<RGB> {
+        self.disguises.get(name).map(|d| d.color)
+    }
+
+    /// Reveals what items called `name` are, returning their disguise if the
+    /// player didn't know it already
+    pub fn identify(&mut self, name: &str) -> Option<String> {
+        let disguise = self.disguises.get(name)?.name.clone();
+        if self.known.insert(name.to_string()) {
+            Some(disguise)
+        } else {
+            None
+        }
+    }
+}
+
+/// How an item is listed in the player's menus: what they know it as, how
+/// many there are and how many charges it has left
+pub fn describe_item(ecs: &World, item: Entity) -> String {
+    let name = match ecs.read_storage::<Name>().get(item) {
+        Some(name) => name.name.clone(),
+        None => return String::new(),
+    };
+    if let Some(coins) = ecs.read_storage::<Gold>().get(item) {
+        return format!("{} gold", coins.amount);
+    }
+    let mut label = ecs.fetch::<ItemIdentities>().item_name(
+        &name,
+        ecs.read_storage::<Enchantment>().get(item),
+        ecs.read_storage::<Cursed>().get(item).is_some(),
+    );
+    if ecs.read_storage::<Sharpened>().get(item).is_some() {
+        label = format!("{} (sharpened)", label);
+    }
+    if let Some(stack) = ecs.read_storage::<Stackable>().get(item) {
+        if stack.quantity > 1 {
+            label = format!("{}x {}", stack.quantity, label);
+        }
+    }
+    if let Some(charges) = ecs.read_storage::<Charges>().get(item) {
+        label = format!(
+            "{} ({} charge{})",
+            label,
+            charges.charges,
+            if charges.charges == 1 { "" } else { "s" }
+        );
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_potion_and_scroll_gets_a_disguise_of_its_own() {
+        let identities = ItemIdentities::new(7);
+        let names: BTreeSet<String> = identities
+            .disguises
+            .values()
+            .map(|d| d.name.clone())
+            .collect();
+        assert_eq!(identities.disguises.len(), POTIONS.len() + SCROLLS.len());
+        assert_eq!(names.len(), identities.disguises.len());
+        for potion in POTIONS.iter() {
+            assert!(identities.display_name(potion).ends_with(" Potion"));
+        }
+        for scroll in SCROLLS.iter() {
+            assert!(identities
+                .display_name(scroll)
+                .starts_with("Scroll labelled "));
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_disguises() {
+        let first = ItemIdentities::new(42);
+        let second = ItemIdentities::new(42);
+        for (name, disguise) in first.disguises.iter() {
+            assert_eq!(second.disguises[name].name, disguise.name);
+        }
+    }
+
+    #[test]
+    fn identifying_reveals_the_real_name_once() {
+        let mut identities = ItemIdentities::new(3);
+        let disguise = identities.display_name("Health Potion");
+        assert_ne!(disguise, "Health Potion");
+
+        assert_eq!(identities.identify("Health Potion"), Some(disguise));
+        assert_eq!(identities.display_name("Health Potion"), "Health Potion");
+        assert_eq!(identities.identify("Health Potion"), None);
+    }
+
+    #[test]
+    fn undisguised_items_keep_their_names() {
+        let mut identities = ItemIdentities::new(3);
+        assert_eq!(identities.display_name("Dagger"), "Dagger");
+        assert_eq!(identities.identify("Dagger"), None);
+    }
+
+    #[test]
+    fn enchantments_show_once_known() {
+        let identities = ItemIdentities::new(3);
+        let hidden = Enchantment {
+            bonus: 2,
+            known: false,
+        };
+        let known = Enchantment {
+            bonus: -1,
+            known: true,
+        };
+        assert_eq!(
+            identities.item_name("Dagger", Some(&hidden), false),
+            "Dagger"
+        );
+        assert_eq!(
+            identities.item_name("Dagger", Some(&known), true),
+            "-1 Dagger (cursed)"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use super::*;
+use rltk::{Point, Rltk, VirtualKeyCode, RGB};
+
+/// Width of the component panel, border included
+const PANEL_WIDTH: i32 = 38;
+
+/// Every registered component `entity` has, by name, with its fields written
+/// out. Serializable components are pretty-printed as JSON
+pub fn inspect_components(ecs: &World, entity: Entity) -> Vec<(&'static str, Vec<String>)> {
+    let mut found: Vec<(&'static str, Vec<String>)> = Vec::new();
+
+    // Components that only save through markers are shown with Debug instead
+    macro_rules! debugged {
+        ($($component:ty),*) => {
+            $(
+                if let Some(component) = ecs.read_storage::<$component>().get(entity) {
+                    let text = format!("{:?}", component);
+                    let fields = text.trim_start_matches(stringify!($component)).trim();
+                    let lines = if fields.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![fields.to_string()]
+                    };
+                    found.push((stringify!($component), lines));
+                }
+            )*
+        };
+    }
+    macro_rules! serialized {
+        ($($component:ty),*) => {
+            $(
+                if let Some(component) = ecs.read_storage::<$component>().get(entity) {
+                    let text = serde_json::to_string_pretty(component).unwrap_or_default();
+                    let lines = if text == "{}" {
+                        Vec::new()
+                    } else {
+                        text.lines().map(|line| line.to_string()).collect()
+                    };
+                    found.push((stringify!($component), lines));
+                }
+            )*
+        };
+    }
+
+    debugged!(
+        Name,
+        Position,
+        CombatStats,
+        Renderable,
+        SufferDamage,
+        WantsToMelee,
+        WantsToPickupItem,
+        WantsToDropItem,
+        WantsToUseItem,
+        WantsToRemoveItem,
+        InBackpack,
+        Equipped,
+        Ranged,
+        InflictsDamage,
+        AreaOfEffect,
+        Confusion,
+        MeleePowerBonus,
+        DefenseBonus,
+        LightSource
+    );
+
+    // The visible tiles would swamp the panel, so they're only counted
+    if let Some(viewshed) = ecs.read_storage::<Viewshed>().get(entity) {
+        found.push((
+            "Viewshed",
+            vec![
+                format!("range: {}", viewshed.range),
+                format!("dirty: {}", viewshed.dirty),
+                format!("visible tiles: {}", viewshed.visible_tiles.len()),
+            ],
+        ));
+    }
+
+    serialized!(
+        Player,
+        MonsterAI,
+        Awareness,
+        Stealth,
+        Faction,
+        HungerClock,
+        BlocksTile,
+        Item,
+        Consumable,
+        ProvidesHealing,
+        ProvidesFood,
+        MagicMapper,
+        IdentifiesItems,
+        Enchantment,
+        Cursed,
+        RemovesCurse,
+        Stackable,
+        Charges,
+        Weight,
+        Value,
+        Encumbrance,
+        Regeneration,
+        Sustenance,
+        Speed,
+        TwoHanded,
+        OffHand,
+        Gold,
+        Purse,
+        Vendor,
+        Sharpened,
+        Equippable,
+        ParticleLifetime,
+        Hidden,
+        EntryTrigger,
+        EntityMoved,
+        SingleActivation,
+        PeriodicHiding,
+        RevealChance,
+        ItemCollector,
+        SpellCaster,
+        GodMode
+    );
+    found
+}
+
+/// The entity worth inspecting at `pos`: something that fights, then an
+/// item, then anything else that isn't a particle
+fn entity_at(ecs: &World, pos: Point) -> Option<Entity> {
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let items = ecs.read_storage::<Item>();
+    let particles = ecs.read_storage::<ParticleLifetime>();
+
+    (&entities, &positions)
+        .join()
+        .filter(|(entity, p)| p.x == pos.x && p.y == pos.y && particles.get(*entity).is_none())
+        .max_by_key(|(entity, _)| {
+            if combat_stats.get(*entity).is_some() {
+                2
+            } else if items.get(*entity).is_some() {
+                1
+            } else {
+                0
+            }
+        })
+        .map(|(entity, _)| entity)
+}
+
+/// The path a monster would take towards where it's heading, worked out the
+/// same way its AI does
+fn current_path(ecs: &World, entity: Entity) -> Vec<usize> {
+    let target = match ecs.read_storage::<MonsterAI>().get(entity) {
+        Some(MonsterAI {
+            target_point: Some(target),
+        }) => *target,
+        _ => return Vec::new(),
+    };
+    let (start_x, start_y) = match ecs.read_storage::<Position>().get(entity) {
+        Some(pos) => (pos.x, pos.y),
+        None => return Vec::new(),
+    };
+
+    let mut map = ecs.fetch_mut::<Map>();
+    let start_idx = map.xy_idx(start_x, start_y);
+    let target_idx = map.xy_idx(target.x, target.y);
+    let target_blocked = map.blocked[target_idx];
+    map.blocked[target_idx] = false;
+    let path = rltk::a_star_search(start_idx, target_idx, &*map);
+    map.blocked[target_idx] = target_blocked;
+
+    if path.success {
+        path.steps
+    } else {
+        Vec::new()
+    }
+}
+
+/// Lets the developer click on an entity to list its components, optionally
+/// highlighting what it can see and where it's going
+pub fn inspector(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    selected: Option<Entity>,
+    show_viewshed: bool,
+    show_path: bool,
+    scroll: usize,
+) -> RunState {
+    let selected = selected.filter(|entity| gs.ecs.entities().is_alive(*entity));
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+
+    // Highlights go on the overlay so they show in both render modes
+    ctx.set_active_console(OVERLAY_CONSOLE);
+    if let Some(entity) = selected {
+        if show_viewshed {
+            if let Some(viewshed) = gs.ecs.read_storage::<Viewshed>().get(entity) {
+                for tile in viewshed.visible_tiles.iter() {
+                    if let Some(screen) = world_to_screen(&gs.ecs, *tile) {
+                        ctx.set_bg(screen.x, screen.y, RGB::named(rltk::NAVY));
+                    }
+                }
+            }
+        }
+        if show_path {
+            let width = gs.ecs.fetch::<Map>().width;
+            for idx in current_path(&gs.ecs, entity) {
+                let tile = Point::new(idx as i32 % width, idx as i32 / width);
+                if let Some(screen) = world_to_screen(&gs.ecs, tile) {
+                    ctx.set_bg(screen.x, screen.y, RGB::named(rltk::OLIVE));
+                }
+            }
+        }
+        if let Some(pos) = gs.ecs.read_storage::<Position>().get(entity) {
+            if let Some(screen) = world_to_screen(&gs.ecs, Point::new(pos.x, pos.y)) {
+                ctx.set_bg(screen.x, screen.y, RGB::named(rltk::MAGENTA));
+            }
+        }
+    }
+    ctx.set_active_console(ASCII_CONSOLE);
+
+    // Keep the panel on the side away from the mouse
+    let mouse_pos = ctx.mouse_pos();
+    let panel_x = if mouse_pos.0 < VIEW_WIDTH / 2 {
+        VIEW_WIDTH - PANEL_WIDTH
+    } else {
+        0
+    };
+    let panel_height = VIEW_HEIGHT - 1;
+    ctx.draw_box(panel_x, 0, PANEL_WIDTH - 1, panel_height, white, black);
+    ctx.print_color(panel_x + 2, 0, RGB::named(rltk::YELLOW), black, "Inspector");
+    ctx.print_color(
+        panel_x + 2,
+        panel_height,
+        RGB::named(rltk::GRAY),
+        black,
+        format!(
+            "V:sight{} P:path{} Esc:close",
+            if show_viewshed { "*" } else { "" },
+            if show_path { "*" } else { "" }
+        ),
+    );
+
+    let text_width = (PANEL_WIDTH - 3) as usize;
+    let mut lines: Vec<(RGB, String)> = Vec::new();
+    match selected {
+        None => lines.push((white, "Click on something to inspect it".to_string())),
+        Some(entity) => {
+            lines.push((white, format!("Entity {}", entity.id())));
+            for (name, fields) in inspect_components(&gs.ecs, entity) {
+                lines.push((RGB::named(rltk::CYAN), name.to_string()));
+                for field in fields {
+                    // Wrap long values onto further lines
+                    let chars: Vec<char> = field.chars().collect();
+                    for chunk in chars.chunks(text_width - 1) {
+                        lines.push((white, format!(" {}", chunk.iter().collect::<String>())));
+                    }
+                }
+            }
+        }
+    }
+    let rows = (panel_height - 1) as usize;
+    let scroll = usize::min(scroll, lines.len().saturating_sub(rows));
+    for (i, (fg, line)) in lines.iter().skip(scroll).take(rows).enumerate() {
+        ctx.print_color(panel_x + 1, 1 + i as i32, *fg, black, line);
+    }
+
+    if ctx.left_click {
+        let clicked = screen_to_world(&gs.ecs, Point::new(mouse_pos.0, mouse_pos.1));
+        let on_panel = mouse_pos.0 >= panel_x && mouse_pos.0 < panel_x + PANEL_WIDTH;
+        if !on_panel && mouse_pos.1 < VIEW_HEIGHT {
+            return RunState::ShowInspector {
+                entity: entity_at(&gs.ecs, clicked),
+                show_viewshed,
+                show_path,
+                scroll: 0,
+            };
+        }
+    }
+
+    let (show_viewshed, show_path, scroll) = match ctx.key {
+        None => (show_viewshed, show_path, scroll),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => return RunState::AwaitingInput,
+            VirtualKeyCode::V => (!show_viewshed, show_path, scroll),
+            VirtualKeyCode::P => (show_viewshed, !show_path, scroll),
+            VirtualKeyCode::Up => (show_viewshed, show_path, scroll.saturating_sub(1)),
+            VirtualKeyCode::Down => (show_viewshed, show_path, scroll + 1),
+            _ => (show_viewshed, show_path, scroll),
+        },
+    };
+    RunState::ShowInspector {
+        entity: selected,
+        show_viewshed,
+        show_path,
+        scroll,
+    }
+}
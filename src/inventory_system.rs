@@ -1,12 +1,15 @@
 use crate::MagicMapper;
 
 use super::{
-    gamelog::GameLog, AreaOfEffect, CombatStats, Confusion, Consumable, Equippable, Equipped,
-    HungerClock, HungerState, InBackpack, InflictsDamage, Map, Name, ParticleBuilder, Position,
-    ProvidesFood, ProvidesHealing, RunState, SufferDamage, WantsToDropItem, WantsToPickupItem,
-    WantsToRemoveItem, WantsToUseItem,
+    gamelog::GameLog, stack_weight, AreaOfEffect, Charges, CombatStats, Confusion, Consumable,
+    Cursed, DamageSource, Enchantment, Encumbrance, EquipmentSlot, Equippable, Equipped, Gold,
+    HungerClock, HungerState, IdentifiesItems, InBackpack, InflictsDamage, Item, ItemIdentities,
+    Map, Name, OffHand, ParticleBuilder, Position, ProvidesFood, ProvidesHealing, Purse, Ranged,
+    RemovesCurse, Renderable, RunState, RunStats, SerializeMe, Stackable, SufferDamage, TwoHanded,
+    Value, WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem, Weight,
 };
 use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
 
 pub struct ItemCollectionSystem {}
 
@@ -19,27 +22,128 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, RunStats>,
+        ReadExpect<'a, ItemIdentities>,
+        ReadStorage<'a, Enchantment>,
+        ReadStorage<'a, Cursed>,
+        Entities<'a>,
+        WriteStorage<'a, Stackable>,
+        ReadStorage<'a, Weight>,
+        WriteStorage<'a, Encumbrance>,
+        ReadStorage<'a, Gold>,
+        WriteStorage<'a, Purse>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
-            data;
+        let (
+            player_entity,
+            mut gamelog,
+            mut wants_pickup,
+            mut positions,
+            names,
+            mut backpack,
+            map,
+            mut run_stats,
+            identities,
+            enchantments,
+            cursed,
+            entities,
+            mut stackables,
+            weights,
+            mut encumbrances,
+            gold,
+            mut purses,
+        ) = data;
 
         for pickup in wants_pickup.join() {
+            let witnessed = match positions.get(pickup.item) {
+                Some(pos) => map.visible_tiles[map.xy_idx(pos.x, pos.y)],
+                None => false,
+            };
+            let item_name = &names.get(pickup.item).unwrap().name;
+            let shown_name = identities.item_name(
+                item_name,
+                enchantments.get(pickup.item),
+                cursed.get(pickup.item).is_some(),
+            );
+            let quantity = stackables.get(pickup.item).map_or(1, |s| s.quantity);
+
+            // Nothing goes in a full pack
+            let weight = stack_weight(weights.get(pickup.item), stackables.get(pickup.item));
+            if let Some(encumbrance) = encumbrances.get_mut(pickup.collected_by) {
+                if encumbrance.load + weight > encumbrance.capacity {
+                    if pickup.collected_by == *player_entity {
+                        gamelog.entries.push(format!(
+                            "You can't carry the {} as well, it's too heavy.",
+                            shown_name
+                        ));
+                    }
+                    continue;
+                }
+                encumbrance.load += weight;
+            }
+
+            // Coins go straight in the collector's purse, if they keep one
+            if let (Some(coins), Some(purse)) =
+                (gold.get(pickup.item), purses.get_mut(pickup.collected_by))
+            {
+                purse.gold += coins.amount;
+                positions.remove(pickup.item);
+                entities.delete(pickup.item).expect("Delete failed");
+                if pickup.collected_by == *player_entity {
+                    gamelog
+                        .entries
+                        .push(format!("You pick up {} gold.", coins.amount));
+                }
+                continue;
+            }
+
+            // Stackable items join a stack of the same thing if the collector
+            // carries one
+            let stack = if stackables.get(pickup.item).is_some() {
+                (&entities, &backpack, &names, &stackables)
+                    .join()
+                    .find(|(_, pack, name, _)| {
+                        pack.owner == pickup.collected_by && name.name == *item_name
+                    })
+                    .map(|(stack, _, _, _)| stack)
+            } else {
+                None
+            };
             positions.remove(pickup.item);
-            backpack
-                .insert(
-                    pickup.item,
-                    InBackpack {
-                        owner: pickup.collected_by,
-                    },
-                )
-                .expect("Unable to insert backpack entry");
+            match stack {
+                Some(stack) => {
+                    if let Some(stack) = stackables.get_mut(stack) {
+                        stack.quantity += quantity;
+                    }
+                    entities.delete(pickup.item).expect("Delete failed");
+                }
+                None => {
+                    backpack
+                        .insert(
+                            pickup.item,
+                            InBackpack {
+                                owner: pickup.collected_by,
+                            },
+                        )
+                        .expect("Unable to insert backpack entry");
+                }
+            }
 
+            let shown_name = if quantity > 1 {
+                format!("{}x {}", quantity, shown_name)
+            } else {
+                format!("the {}", shown_name)
+            };
             if pickup.collected_by == *player_entity {
+                gamelog.entries.push(format!("You pick up {}.", shown_name));
+                *run_stats.items_found.entry(item_name.clone()).or_insert(0) += quantity;
+            } else if witnessed {
                 gamelog.entries.push(format!(
-                    "You pick up the {}.",
-                    names.get(pickup.item).unwrap().name
+                    "The {} picks up {}.",
+                    names.get(pickup.collected_by).unwrap().name,
+                    shown_name
                 ));
             }
         }
@@ -67,7 +171,12 @@ impl<'a> System<'a> for ItemUseSystem {
         WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, Confusion>,
-        ReadStorage<'a, Equippable>,
+        // What can be worn, and which hands weapons take
+        (
+            ReadStorage<'a, Equippable>,
+            ReadStorage<'a, TwoHanded>,
+            ReadStorage<'a, OffHand>,
+        ),
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
         WriteExpect<'a, ParticleBuilder>,
@@ -75,6 +184,17 @@ impl<'a> System<'a> for ItemUseSystem {
         ReadStorage<'a, ProvidesFood>,
         WriteStorage<'a, HungerClock>,
         ReadStorage<'a, MagicMapper>,
+        WriteExpect<'a, RunStats>,
+        WriteStorage<'a, Stackable>,
+        WriteStorage<'a, Charges>,
+        // Scrolls that act on the user's own belongings, and what they reveal
+        (
+            WriteExpect<'a, ItemIdentities>,
+            ReadStorage<'a, IdentifiesItems>,
+            ReadStorage<'a, RemovesCurse>,
+            WriteStorage<'a, Cursed>,
+            WriteStorage<'a, Enchantment>,
+        ),
     );
 
     #[allow(clippy::cognitive_complexity)]
@@ -94,7 +214,7 @@ impl<'a> System<'a> for ItemUseSystem {
             mut suffer_damage,
             aoe,
             mut confused,
-            equippables,
+            (equippables, two_handed, off_hand),
             mut equipped_store,
             mut in_backpack_store,
             mut particle_builder,
@@ -102,11 +222,88 @@ impl<'a> System<'a> for ItemUseSystem {
             provides_food,
             mut hunger_clocks,
             magic_mapper,
+            mut run_stats,
+            mut stackables,
+            mut charges_store,
+            (mut identities, identifies_items, removes_curse, mut cursed, mut enchantments),
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
             let mut used_item = true;
 
+            if entity == *player_entity {
+                if let Some(name) = names.get(useitem.item) {
+                    *run_stats.items_used.entry(name.name.clone()).or_insert(0) += 1;
+
+                    // Trying something out is enough to learn what it is
+                    if let Some(disguise) = identities.identify(&name.name) {
+                        gamelog
+                            .entries
+                            .push(format!("The {} is a {}!", disguise, name.name));
+                    }
+                }
+            }
+            let item_name = identities.display_name(&names.get(useitem.item).unwrap().name);
+
+            // If it identifies things, reveal everything the user carries
+            if identifies_items.get(useitem.item).is_some() && entity == *player_entity {
+                used_item = true;
+                let mut learned = false;
+                for (carried, name, _) in (&entities, &names, &in_backpack_store)
+                    .join()
+                    .filter(|(carried, _, pack)| pack.owner == entity && *carried != useitem.item)
+                {
+                    let before = identities.item_name(
+                        &name.name,
+                        enchantments.get(carried),
+                        cursed.get(carried).is_some(),
+                    );
+                    identities.identify(&name.name);
+                    if let Some(enchantment) = enchantments.get_mut(carried) {
+                        enchantment.known = true;
+                    }
+                    let after = identities.item_name(
+                        &name.name,
+                        enchantments.get(carried),
+                        cursed.get(carried).is_some(),
+                    );
+                    if before != after {
+                        gamelog
+                            .entries
+                            .push(format!("The {} is a {}.", before, after));
+                        learned = true;
+                    }
+                }
+                if !learned {
+                    gamelog.entries.push("You learn nothing new.".to_string());
+                }
+            }
+
+            // If it lifts curses, free everything the user carries or wears
+            if removes_curse.get(useitem.item).is_some() {
+                used_item = true;
+                let lifted: Vec<Entity> = (&entities, &cursed)
+                    .join()
+                    .map(|(item, _)| item)
+                    .filter(|item| {
+                        matches!(in_backpack_store.get(*item), Some(pack) if pack.owner == entity)
+                            || matches!(equipped_store.get(*item), Some(worn) if worn.owner == entity)
+                    })
+                    .collect();
+                for item in lifted.iter() {
+                    cursed.remove(*item);
+                }
+                if entity == *player_entity {
+                    if lifted.is_empty() {
+                        gamelog.entries.push("Nothing seems to happen.".to_string());
+                    } else {
+                        gamelog
+                            .entries
+                            .push("You feel a malevolent aura lift from your gear.".to_string());
+                    }
+                }
+            }
+
             // If its a magic mapper...
             let is_mapper = magic_mapper.get(useitem.item);
             match is_mapper {
@@ -120,11 +317,24 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
 
+            // Let the player know when they see a monster use something
+            if entity != *player_entity {
+                if let Some(pos) = positions.get(entity) {
+                    if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                        gamelog.entries.push(format!(
+                            "The {} uses the {}.",
+                            names.get(entity).unwrap().name,
+                            item_name
+                        ));
+                    }
+                }
+            }
+
             // Targeting
             let mut targets: Vec<Entity> = Vec::new();
             match useitem.target {
                 None => {
-                    targets.push(*player_entity);
+                    targets.push(entity);
                 }
                 Some(target) => {
                     let area_effect = aoe.get(useitem.item);
@@ -171,31 +381,107 @@ impl<'a> System<'a> for ItemUseSystem {
             match item_equippable {
                 None => {}
                 Some(can_equip) => {
-                    let target_slot = can_equip.slot;
                     let target = targets[0];
-
-                    // Remove any items the target has in the item's slot
-                    let mut to_unequip: Vec<Entity> = Vec::new();
-                    for (item_entity, already_equipped, name) in
-                        (&entities, &equipped_store, &names).join()
+                    let item_two_handed = two_handed.get(useitem.item).is_some();
+                    let item_name = identities.item_name(
+                        &names.get(useitem.item).unwrap().name,
+                        enchantments.get(useitem.item),
+                        cursed.get(useitem.item).is_some(),
+                    );
+
+                    // A weapon held in both hands leaves the off hand full too
+                    let wielding_two_handed =
+                        (&entities, &equipped_store).join().any(|(item, worn)| {
+                            worn.owner == target
+                                && worn.slot == EquipmentSlot::Melee
+                                && two_handed.get(item).is_some()
+                        });
+                    let occupied = |slot: EquipmentSlot| {
+                        equipped_store
+                            .join()
+                            .any(|worn| worn.owner == target && worn.slot == slot)
+                            || (slot == EquipmentSlot::Shield && wielding_two_handed)
+                    };
+
+                    // Take the first free slot the item fits, or swap out
+                    // whatever is in its own. Light weapons fit the off hand
+                    // once the main one is full
+                    let slots = if off_hand.get(useitem.item).is_some() {
+                        &[EquipmentSlot::Melee, EquipmentSlot::Shield]
+                    } else {
+                        can_equip.slot.fits()
+                    };
+                    let target_slot = slots
+                        .iter()
+                        .copied()
+                        .find(|slot| !occupied(*slot))
+                        .unwrap_or(can_equip.slot);
+
+                    // Find what the target has in the item's slot, and in the
+                    // other hand if either weapon needs both
+                    let worn: Vec<(Entity, String, String)> = (&entities, &equipped_store, &names)
+                        .join()
+                        .filter(|(item_entity, already_equipped, _)| {
+                            already_equipped.owner == target
+                                && (already_equipped.slot == target_slot
+                                    || (item_two_handed
+                                        && already_equipped.slot == EquipmentSlot::Shield)
+                                    || (target_slot == EquipmentSlot::Shield
+                                        && two_handed.get(*item_entity).is_some()))
+                        })
+                        .map(|(item_entity, already_equipped, name)| {
+                            let name = identities.item_name(
+                                &name.name,
+                                enchantments.get(item_entity),
+                                cursed.get(item_entity).is_some(),
+                            );
+                            // Say why something outside the item's own
+                            // slot has to come off
+                            let reason = if already_equipped.slot == target_slot {
+                                String::new()
+                            } else if item_two_handed {
+                                format!("You need both hands to wield the {}.", item_name)
+                            } else {
+                                format!(
+                                    "You need a free hand for the {}, and the {} takes both.",
+                                    item_name, name
+                                )
+                            };
+                            (item_entity, name, reason)
+                        })
+                        .collect();
+
+                    // A cursed item won't come off to make room
+                    if let Some((_, name, reason)) =
+                        worn.iter().find(|(item, _, _)| cursed.get(*item).is_some())
                     {
-                        if already_equipped.owner == target && already_equipped.slot == target_slot
-                        {
-                            to_unequip.push(item_entity);
-                            if target == *player_entity {
-                                gamelog.entries.push(format!("You unequip {}.", name.name));
-                            }
+                        if target == *player_entity && !reason.is_empty() {
+                            gamelog.entries.push(reason.clone());
                         }
+                        if target == *player_entity {
+                            gamelog.entries.push(format!(
+                                "You can't take off the {}. It is stuck to your hand!",
+                                name
+                            ));
+                        }
+                        continue;
                     }
+
                     // Unequip items-to-unequip
-                    for item in to_unequip.iter() {
+                    for (item, name, reason) in worn.iter() {
                         equipped_store.remove(*item);
                         in_backpack_store
                             .insert(*item, InBackpack { owner: target })
                             .expect("Unable to insert backpack entry");
+                        if target == *player_entity {
+                            if !reason.is_empty() {
+                                gamelog.entries.push(reason.clone());
+                            }
+                            gamelog.entries.push(format!("You unequip {}.", name));
+                        }
                     }
 
-                    // Wield the item
+                    // Wield the item, learning how enchanted it is
                     equipped_store
                         .insert(
                             useitem.item,
@@ -207,10 +493,26 @@ impl<'a> System<'a> for ItemUseSystem {
                         .expect("Unable to insert equipped component");
                     in_backpack_store.remove(useitem.item);
                     if target == *player_entity {
-                        gamelog.entries.push(format!(
-                            "You equip {}.",
-                            names.get(useitem.item).unwrap().name
-                        ));
+                        if let Some(enchantment) = enchantments.get_mut(useitem.item) {
+                            enchantment.known = true;
+                        }
+                        let name = identities.item_name(
+                            &names.get(useitem.item).unwrap().name,
+                            enchantments.get(useitem.item),
+                            cursed.get(useitem.item).is_some(),
+                        );
+                        if target_slot == EquipmentSlot::Shield
+                            && off_hand.get(useitem.item).is_some()
+                        {
+                            gamelog
+                                .entries
+                                .push(format!("You equip {} in your off hand.", name));
+                        } else {
+                            gamelog.entries.push(format!("You equip {}.", name));
+                        }
+                        if cursed.get(useitem.item).is_some() {
+                            gamelog.entries.push("It's cursed!".to_string());
+                        }
                     }
                 }
             }
@@ -222,12 +524,14 @@ impl<'a> System<'a> for ItemUseSystem {
                 for target in targets.iter() {
                     let stats = combat_stats.get_mut(*target);
                     if let Some(stats) = stats {
+                        if *target == *player_entity && stats.hp == stats.max_hp {
+                            run_stats.potions_wasted += 1;
+                        }
                         stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
                         if entity == *player_entity {
                             gamelog.entries.push(format!(
                                 "You use the {}, healing {} hp.",
-                                names.get(useitem.item).unwrap().name,
-                                healer.heal_amount
+                                item_name, healer.heal_amount
                             ));
                         }
                         // Add a heart particle effect
@@ -251,14 +555,33 @@ impl<'a> System<'a> for ItemUseSystem {
             let item_damages = inflict_damage.get(useitem.item);
             if let Some(damage) = item_damages {
                 used_item = false;
+                // Statistics are kept by what the item really is, not by
+                // this run's disguise for it
+                let cause = match names.get(entity) {
+                    Some(user) => format!(
+                        "a {} used by {}",
+                        names.get(useitem.item).unwrap().name,
+                        user.name
+                    ),
+                    None => "magic".to_string(),
+                };
                 for mob in targets.iter() {
-                    SufferDamage::new_damage(&mut suffer_damage, *mob, damage.damage);
+                    SufferDamage::new_damage(
+                        &mut suffer_damage,
+                        *mob,
+                        damage.damage,
+                        DamageSource::new(&cause, entity == *player_entity),
+                    );
                     if entity == *player_entity {
                         let mob_name = names.get(*mob).unwrap();
-                        let item_name = names.get(useitem.item).unwrap();
                         gamelog.entries.push(format!(
                             "You use {} on {}, inflicting {} hp.",
-                            item_name.name, mob_name.name, damage.damage
+                            item_name, mob_name.name, damage.damage
+                        ));
+                    } else if *mob == *player_entity {
+                        gamelog.entries.push(format!(
+                            "The {} hits you, inflicting {} hp.",
+                            item_name, damage.damage
                         ));
                     }
 
@@ -289,10 +612,13 @@ impl<'a> System<'a> for ItemUseSystem {
                     if let Some(hc) = hc {
                         hc.state = HungerState::WellFed;
                         hc.duration = 20;
-                        gamelog.entries.push(format!(
-                            "You eat the {}.",
-                            names.get(useitem.item).unwrap().name
-                        ));
+                        if target == *player_entity {
+                            *run_stats
+                                .hunger_states
+                                .entry(format!("{:?}", hc.state))
+                                .or_insert(0) += 1;
+                        }
+                        gamelog.entries.push(format!("You eat the {}.", item_name));
                     }
                 }
             }
@@ -307,10 +633,9 @@ impl<'a> System<'a> for ItemUseSystem {
                         add_confusion.push((*mob, confusion.turns));
                         if entity == *player_entity {
                             let mob_name = names.get(*mob).unwrap();
-                            let item_name = names.get(useitem.item).unwrap();
                             gamelog.entries.push(format!(
                                 "You use {} on {}, confusing them.",
-                                item_name.name, mob_name.name
+                                item_name, mob_name.name
                             ));
 
                             // Add a confused particle effect
@@ -337,12 +662,27 @@ impl<'a> System<'a> for ItemUseSystem {
                     .expect("Unable to insert status");
             }
 
-            // If its a consumable, we delete it on use
+            // If its a consumable, we use one up, deleting it when the stack
+            // runs out
             if used_item {
                 let consumable = consumables.get(useitem.item);
                 match consumable {
                     None => {}
-                    Some(_) => {
+                    Some(_) => match stackables.get_mut(useitem.item) {
+                        Some(stack) if stack.quantity > 1 => stack.quantity -= 1,
+                        _ => entities.delete(useitem.item).expect("Delete failed"),
+                    },
+                }
+
+                // Wands and staves spend a charge instead
+                if let Some(charges) = charges_store.get_mut(useitem.item) {
+                    charges.charges -= 1;
+                    if charges.charges <= 0 {
+                        if entity == *player_entity {
+                            gamelog
+                                .entries
+                                .push(format!("The {} crumbles to dust.", item_name));
+                        }
                         entities.delete(useitem.item).expect("Delete failed");
                     }
                 }
@@ -365,6 +705,9 @@ impl<'a> System<'a> for ItemDropSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, InBackpack>,
+        ReadExpect<'a, ItemIdentities>,
+        ReadStorage<'a, Enchantment>,
+        ReadStorage<'a, Cursed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -376,6 +719,9 @@ impl<'a> System<'a> for ItemDropSystem {
             names,
             mut positions,
             mut backpack,
+            identities,
+            enchantments,
+            cursed,
         ) = data;
 
         for (entity, to_drop) in (&entities, &wants_drop).join() {
@@ -399,7 +745,11 @@ impl<'a> System<'a> for ItemDropSystem {
             if entity == *player_entity {
                 gamelog.entries.push(format!(
                     "You drop the {}.",
-                    names.get(to_drop.item).unwrap().name
+                    identities.item_name(
+                        &names.get(to_drop.item).unwrap().name,
+                        enchantments.get(to_drop.item),
+                        cursed.get(to_drop.item).is_some(),
+                    )
                 ));
             }
         }
@@ -413,16 +763,36 @@ pub struct ItemRemoveSystem {}
 impl<'a> System<'a> for ItemRemoveSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
         Entities<'a>,
         WriteStorage<'a, WantsToRemoveItem>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, Cursed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_remove, mut equipped, mut backpack) = data;
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_remove,
+            mut equipped,
+            mut backpack,
+            cursed,
+        ) = data;
 
         for (entity, to_remove) in (&entities, &wants_remove).join() {
+            // Cursed items stay put until the curse is lifted
+            if cursed.get(to_remove.item).is_some() {
+                if entity == *player_entity {
+                    gamelog
+                        .entries
+                        .push("It is stuck to your hand!".to_string());
+                }
+                continue;
+            }
             equipped.remove(to_remove.item);
             backpack
                 .insert(to_remove.item, InBackpack { owner: entity })
@@ -432,3 +802,159 @@ impl<'a> System<'a> for ItemRemoveSystem {
         wants_remove.clear();
     }
 }
+
+/// Takes `quantity` items off the stack `item` into a stack of their own,
+/// carried by the same owner, and returns the new stack
+pub fn split_stack(ecs: &mut World, item: Entity, quantity: i32) -> Entity {
+    let split = ecs
+        .create_entity()
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    // Everything a stackable item can be made of comes along
+    macro_rules! copy_components {
+        ($($component:ty),*) => {
+            $(
+                let component = ecs.read_storage::<$component>().get(item).cloned();
+                if let Some(component) = component {
+                    ecs.write_storage::<$component>()
+                        .insert(split, component)
+                        .expect("Unable to copy component");
+                }
+            )*
+        };
+    }
+    copy_components!(
+        Name,
+        Renderable,
+        Item,
+        Consumable,
+        Stackable,
+        ProvidesHealing,
+        ProvidesFood,
+        Ranged,
+        InflictsDamage,
+        AreaOfEffect,
+        Confusion,
+        MagicMapper,
+        IdentifiesItems,
+        RemovesCurse,
+        Weight,
+        Value
+    );
+
+    let owner = ecs.read_storage::<InBackpack>().get(item).map(|p| p.owner);
+    if let Some(owner) = owner {
+        ecs.write_storage::<InBackpack>()
+            .insert(split, InBackpack { owner })
+            .expect("Unable to insert backpack entry");
+    }
+    let mut stackables = ecs.write_storage::<Stackable>();
+    if let Some(stack) = stackables.get_mut(item) {
+        stack.quantity -= quantity;
+    }
+    if let Some(stack) = stackables.get_mut(split) {
+        stack.quantity = quantity;
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawner, test_support::test_world};
+
+    /// Spawns `quantity` of the stackable item called `name` at 1, 1
+    fn spawn_stack(ecs: &mut World, name: &str, quantity: i32) -> Entity {
+        let item = spawner::spawn_named(ecs, name, 1, 1).unwrap();
+        ecs.write_storage::<Stackable>()
+            .get_mut(item)
+            .unwrap()
+            .quantity = quantity;
+        item
+    }
+
+    fn pick_up(ecs: &mut World, item: Entity) {
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<WantsToPickupItem>()
+            .insert(
+                player,
+                WantsToPickupItem {
+                    collected_by: player,
+                    item,
+                },
+            )
+            .unwrap();
+        ItemCollectionSystem {}.run_now(ecs);
+        ecs.maintain();
+    }
+
+    /// Quantities of everything called `name` in the player's backpack
+    fn carried(ecs: &World, name: &str) -> Vec<i32> {
+        let player = *ecs.fetch::<Entity>();
+        let names = ecs.read_storage::<Name>();
+        let stackables = ecs.read_storage::<Stackable>();
+        (&ecs.entities(), &ecs.read_storage::<InBackpack>(), &names)
+            .join()
+            .filter(|(_, pack, n)| pack.owner == player && n.name == name)
+            .map(|(item, _, _)| stackables.get(item).map_or(1, |s| s.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn splitting_a_stack_leaves_the_rest_behind() {
+        let mut ecs = test_world();
+        let stack = spawn_stack(&mut ecs, "Health Potion", 5);
+        let player = *ecs.fetch::<Entity>();
+        spawner::give_item(&mut ecs, player, stack);
+
+        let split = split_stack(&mut ecs, stack, 2);
+        let stackables = ecs.read_storage::<Stackable>();
+        assert_eq!(stackables.get(stack).unwrap().quantity, 3);
+        assert_eq!(stackables.get(split).unwrap().quantity, 2);
+        assert_eq!(
+            ecs.read_storage::<Name>().get(split).unwrap().name,
+            "Health Potion"
+        );
+        assert_eq!(
+            ecs.read_storage::<InBackpack>().get(split).unwrap().owner,
+            *ecs.fetch::<Entity>()
+        );
+    }
+
+    #[test]
+    fn picking_up_joins_a_carried_stack() {
+        let mut ecs = test_world();
+        let stack = spawn_stack(&mut ecs, "Health Potion", 2);
+        let player = *ecs.fetch::<Entity>();
+        spawner::give_item(&mut ecs, player, stack);
+        let more = spawn_stack(&mut ecs, "Health Potion", 3);
+
+        pick_up(&mut ecs, more);
+        assert_eq!(carried(&ecs, "Health Potion"), vec![5]);
+        assert!(!ecs.is_alive(more));
+    }
+
+    #[test]
+    fn different_items_dont_stack() {
+        let mut ecs = test_world();
+        let potions = spawn_stack(&mut ecs, "Health Potion", 2);
+        let player = *ecs.fetch::<Entity>();
+        spawner::give_item(&mut ecs, player, potions);
+        let rations = spawn_stack(&mut ecs, "Rations", 1);
+
+        pick_up(&mut ecs, rations);
+        assert_eq!(carried(&ecs, "Health Potion"), vec![2]);
+        assert_eq!(carried(&ecs, "Rations"), vec![1]);
+    }
+
+    #[test]
+    fn equipment_never_stacks() {
+        let mut ecs = test_world();
+        for _ in 0..2 {
+            let dagger = spawner::spawn_named(&mut ecs, "Dagger", 1, 1).unwrap();
+            pick_up(&mut ecs, dagger);
+        }
+        assert_eq!(carried(&ecs, "Dagger"), vec![1, 1]);
+    }
+}
@@ -0,0 +1,305 @@
+use rltk::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Something the player can do from the map screen with a single key
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthEast,
+    MoveNorthWest,
+    MoveSouthEast,
+    MoveSouthWest,
+    Wait,
+    PickUp,
+    Inventory,
+    Drop,
+    Unequip,
+    Character,
+    Craft,
+    Descend,
+    Sneak,
+    Look,
+    ExportStats,
+    ToggleTiles,
+    Help,
+}
+
+/// Every command, in the order the key binding editor lists them
+pub const ALL_COMMANDS: [Command; 21] = [
+    Command::MoveNorth,
+    Command::MoveSouth,
+    Command::MoveWest,
+    Command::MoveEast,
+    Command::MoveNorthEast,
+    Command::MoveNorthWest,
+    Command::MoveSouthEast,
+    Command::MoveSouthWest,
+    Command::Wait,
+    Command::PickUp,
+    Command::Inventory,
+    Command::Drop,
+    Command::Unequip,
+    Command::Character,
+    Command::Craft,
+    Command::Descend,
+    Command::Sneak,
+    Command::Look,
+    Command::ExportStats,
+    Command::ToggleTiles,
+    Command::Help,
+];
+
+impl Command {
+    /// Name shown in the key binding editor
+    pub fn description(self) -> &'static str {
+        match self {
+            Command::MoveNorth => "Move north",
+            Command::MoveSouth => "Move south",
+            Command::MoveWest => "Move west",
+            Command::MoveEast => "Move east",
+            Command::MoveNorthEast => "Move north-east",
+            Command::MoveNorthWest => "Move north-west",
+            Command::MoveSouthEast => "Move south-east",
+            Command::MoveSouthWest => "Move south-west",
+            Command::Wait => "Wait a turn",
+            Command::PickUp => "Pick up",
+            Command::Inventory => "Inventory",
+            Command::Drop => "Drop item",
+            Command::Unequip => "Unequip item",
+            Command::Character => "Character sheet",
+            Command::Craft => "Craft",
+            Command::Descend => "Go downstairs",
+            Command::Sneak => "Toggle sneaking",
+            Command::Look => "Look around",
+            Command::ExportStats => "Export statistics",
+            Command::ToggleTiles => "Toggle tiles",
+            Command::Help => "Help",
+        }
+    }
+}
+
+/// Keys that can be bound to commands. Escape is left out so the menu can
+/// always be reached
+const BINDABLE_KEYS: [VirtualKeyCode; 79] = [
+    VirtualKeyCode::A,
+    VirtualKeyCode::B,
+    VirtualKeyCode::C,
+    VirtualKeyCode::D,
+    VirtualKeyCode::E,
+    VirtualKeyCode::F,
+    VirtualKeyCode::G,
+    VirtualKeyCode::H,
+    VirtualKeyCode::I,
+    VirtualKeyCode::J,
+    VirtualKeyCode::K,
+    VirtualKeyCode::L,
+    VirtualKeyCode::M,
+    VirtualKeyCode::N,
+    VirtualKeyCode::O,
+    VirtualKeyCode::P,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::R,
+    VirtualKeyCode::S,
+    VirtualKeyCode::T,
+    VirtualKeyCode::U,
+    VirtualKeyCode::V,
+    VirtualKeyCode::W,
+    VirtualKeyCode::X,
+    VirtualKeyCode::Y,
+    VirtualKeyCode::Z,
+    VirtualKeyCode::Key0,
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+    VirtualKeyCode::Numpad0,
+    VirtualKeyCode::Numpad1,
+    VirtualKeyCode::Numpad2,
+    VirtualKeyCode::Numpad3,
+    VirtualKeyCode::Numpad4,
+    VirtualKeyCode::Numpad5,
+    VirtualKeyCode::Numpad6,
+    VirtualKeyCode::Numpad7,
+    VirtualKeyCode::Numpad8,
+    VirtualKeyCode::Numpad9,
+    VirtualKeyCode::Up,
+    VirtualKeyCode::Down,
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Home,
+    VirtualKeyCode::End,
+    VirtualKeyCode::PageUp,
+    VirtualKeyCode::PageDown,
+    VirtualKeyCode::Insert,
+    VirtualKeyCode::Delete,
+    VirtualKeyCode::Space,
+    VirtualKeyCode::Tab,
+    VirtualKeyCode::Return,
+    VirtualKeyCode::Back,
+    VirtualKeyCode::Period,
+    VirtualKeyCode::Comma,
+    VirtualKeyCode::Slash,
+    VirtualKeyCode::Semicolon,
+    VirtualKeyCode::Apostrophe,
+    VirtualKeyCode::LBracket,
+    VirtualKeyCode::RBracket,
+    VirtualKeyCode::Minus,
+    VirtualKeyCode::Equals,
+    VirtualKeyCode::Grave,
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+];
+
+/// Name a key is saved under in the settings file
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Parses a key name from the settings file, if it's a key that can be bound
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|k| key_name(*k) == name)
+}
+
+/// Whether `key` may be bound to a command
+pub fn is_bindable(key: VirtualKeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+/// Which keys trigger which commands. Saved in the settings file as lists of
+/// key names for each command
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[serde(
+    from = "HashMap<Command, Vec<String>>",
+    into = "HashMap<Command, Vec<String>>"
+)]
+pub struct KeyMap {
+    bindings: HashMap<Command, Vec<VirtualKeyCode>>,
+}
+
+impl KeyMap {
+    /// Returns the command `key` is bound to, if any
+    pub fn command(&self, key: VirtualKeyCode) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(command, _)| *command)
+    }
+
+    /// Returns the keys bound to `command`
+    pub fn keys(&self, command: Command) -> &[VirtualKeyCode] {
+        match self.bindings.get(&command) {
+            Some(keys) => keys,
+            None => &[],
+        }
+    }
+
+    /// Binds `key` to `command` as well as its existing keys. Fails with the
+    /// command `key` is already bound to if it would conflict
+    pub fn bind(&mut self, command: Command, key: VirtualKeyCode) -> Result<(), Command> {
+        match self.command(key) {
+            Some(existing) if existing == command => Ok(()),
+            Some(existing) => Err(existing),
+            None => {
+                self.bindings.entry(command).or_default().push(key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes every key bound to `command`
+    pub fn clear(&mut self, command: Command) {
+        self.bindings.remove(&command);
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        use VirtualKeyCode::*;
+        let defaults: [(Command, &[VirtualKeyCode]); 21] = [
+            (Command::MoveNorth, &[Up, Numpad8, K]),
+            (Command::MoveSouth, &[Down, Numpad2, J]),
+            (Command::MoveWest, &[Left, Numpad4, H]),
+            (Command::MoveEast, &[Right, Numpad6, L]),
+            (Command::MoveNorthEast, &[Numpad9, U]),
+            (Command::MoveNorthWest, &[Numpad7, Y]),
+            (Command::MoveSouthEast, &[Numpad3, M]),
+            (Command::MoveSouthWest, &[Numpad1, N]),
+            (Command::Wait, &[Numpad5, Space]),
+            (Command::PickUp, &[G]),
+            (Command::Inventory, &[I]),
+            (Command::Drop, &[D]),
+            (Command::Unequip, &[R]),
+            (Command::Character, &[C]),
+            (Command::Craft, &[B]),
+            (Command::Descend, &[Period]),
+            (Command::Sneak, &[S]),
+            (Command::Look, &[X]),
+            (Command::ExportStats, &[F2]),
+            (Command::ToggleTiles, &[Tab]),
+            (Command::Help, &[Slash, F1]),
+        ];
+
+        KeyMap {
+            bindings: defaults
+                .iter()
+                .map(|(command, keys)| (*command, keys.to_vec()))
+                .collect(),
+        }
+    }
+}
+
+impl From<HashMap<Command, Vec<String>>> for KeyMap {
+    /// Unknown key names and keys already bound to another command are
+    /// dropped. Commands missing from the file keep their default keys
+    fn from(saved: HashMap<Command, Vec<String>>) -> KeyMap {
+        let defaults = KeyMap::default();
+        let mut keymap = KeyMap {
+            bindings: HashMap::new(),
+        };
+
+        for command in ALL_COMMANDS.iter() {
+            if let Some(names) = saved.get(command) {
+                for key in names.iter().filter_map(|n| key_from_name(n)) {
+                    let _ = keymap.bind(*command, key);
+                }
+            }
+        }
+        for command in ALL_COMMANDS.iter() {
+            if !saved.contains_key(command) {
+                for key in defaults.keys(*command) {
+                    let _ = keymap.bind(*command, *key);
+                }
+            }
+        }
+
+        keymap
+    }
+}
+
+impl From<KeyMap> for HashMap<Command, Vec<String>> {
+    fn from(keymap: KeyMap) -> HashMap<Command, Vec<String>> {
+        ALL_COMMANDS
+            .iter()
+            .map(|command| {
+                let names = keymap.keys(*command).iter().map(|k| key_name(*k)).collect();
+                (*command, names)
+            })
+            .collect()
+    }
+}
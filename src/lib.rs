@@ -0,0 +1,922 @@
+#![allow(unused_variables)]
+use rltk::{GameState, Point, Rltk};
+use specs::prelude::*;
+use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
+
+mod save_load_system;
+pub use save_load_system::*;
+mod monster_ai_system;
+pub use monster_ai_system::*;
+mod melee_combat_system;
+pub use melee_combat_system::*;
+mod damage_system;
+pub use damage_system::*;
+mod map_indexing_system;
+pub use map_indexing_system::*;
+mod components;
+pub use components::*;
+mod map;
+pub use map::*;
+mod player;
+pub use player::*;
+mod gui;
+pub use gui::*;
+mod gamelog;
+pub use gamelog::*;
+mod rect;
+pub use rect::Rect;
+mod visibility_system;
+pub use visibility_system::*;
+mod trigger_system;
+pub use trigger_system::*;
+mod spawner;
+pub use spawner::*;
+mod inventory_system;
+pub use inventory_system::*;
+mod random_table;
+pub use random_table::*;
+mod particle_system;
+pub use particle_system::*;
+mod hunger_system;
+pub use hunger_system::*;
+mod rex_assets;
+pub use rex_assets::*;
+pub mod map_builders;
+mod periodic_hiding_system;
+pub use periodic_hiding_system::*;
+mod faction_table;
+pub use faction_table::*;
+mod monster_item_system;
+pub use monster_item_system::*;
+mod noise_system;
+pub use noise_system::*;
+mod lighting_system;
+pub use lighting_system::*;
+mod camera;
+pub use camera::*;
+mod tileset;
+pub use tileset::*;
+mod storage;
+pub use storage::*;
+mod settings;
+pub use settings::*;
+mod keymap;
+pub use keymap::*;
+mod morgue;
+pub use morgue::*;
+mod statistics;
+pub use statistics::*;
+mod identification;
+pub use identification::*;
+mod encumbrance_system;
+pub use encumbrance_system::*;
+mod regeneration_system;
+pub use regeneration_system::*;
+mod shop;
+pub use shop::*;
+mod crafting;
+pub use crafting::*;
+mod highscores;
+pub use highscores::*;
+mod bot;
+pub use bot::*;
+#[cfg(feature = "debug-console")]
+mod debug_console;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "debug-console")]
+pub use debug_console::*;
+#[cfg(feature = "debug-console")]
+mod inspector;
+#[cfg(feature = "debug-console")]
+pub use inspector::*;
+
+/// How long each mapgen snapshot is shown for at first, and the fastest and
+/// slowest playback allowed, in milliseconds
+const MAPGEN_FRAME_MS: f32 = 300.0;
+const MAPGEN_MIN_FRAME_MS: f32 = 25.0;
+const MAPGEN_MAX_FRAME_MS: f32 = 2400.0;
+
+/// Present when the game is simulated without a window, as the bot does.
+/// Nothing is written to disk when a headless game ends
+pub struct Headless {}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum RunState {
+    /// Systems have fully responded to latest player
+    /// inputs and are now waiting for newer input
+    AwaitingInput,
+    /// Initial set up phase
+    PreRun,
+    /// Player has made new inputs and systems need
+    /// to respond
+    PlayerTurn,
+    /// Systems have responded to latest player input
+    /// and now ai (etc.) need to respond
+    MonsterTurn,
+    /// When user has their inventory screen open
+    ShowInventory,
+    /// When user has their drop-item screen open
+    ShowDropItem,
+    /// When user is choosing how much of a stack to drop
+    ShowDropQuantity { item: Entity, quantity: i32 },
+    /// When user has to select a target for a spell
+    ShowTargeting { range: i32, item: Entity },
+    /// When user is looking over the map with a cursor
+    ShowLook { cursor: Point },
+    /// When user is in the main menu screen
+    MainMenu {
+        menu_selection: gui::MainMenuSelection,
+    },
+    /// Initiates saving game
+    SaveGame,
+    /// Initiates loading a new level
+    NextLevel,
+    /// Shows the Item removal menu
+    ShowRemoveItem,
+    /// When user has their character sheet open
+    ShowCharacter,
+    /// When user is trading with a shopkeeper
+    ShowShop { vendor: Entity, mode: gui::ShopMode },
+    /// When user is choosing something to craft
+    ShowCrafting,
+    /// Player has lost
+    GameOver,
+    /// Player has revealed the map
+    MagicMapReveal { row: i32 },
+    /// Generating a new Map
+    MapGeneration,
+    /// When user has the options screen open from the main menu
+    ShowOptions { selection: gui::OptionsSelection },
+    /// When user has the help screen open
+    ShowHelp { page: usize },
+    /// When user is browsing past runs from the main menu
+    ShowHighScores {
+        sort: HighScoreSort,
+        selection: usize,
+    },
+    /// When user is editing key bindings from the options screen
+    ShowKeyBindings {
+        selection: usize,
+        capturing: bool,
+        conflict: Option<Command>,
+    },
+    /// When the developer has the debug console open
+    #[cfg(feature = "debug-console")]
+    ShowDebugConsole,
+    /// When the developer is looking over entities' components
+    #[cfg(feature = "debug-console")]
+    ShowInspector {
+        entity: Option<Entity>,
+        show_viewshed: bool,
+        show_path: bool,
+        scroll: usize,
+    },
+}
+
+pub struct State {
+    /// Specs ECS Storage and Resource data
+    pub ecs: World,
+    // Because we need to know the start which we want to transition to after
+    // visualizing, but enums cannot store cyclic references, so we store in
+    // State. Maybe there's a better way to do this?
+    /// What game should transition to after visualizing a map gen state
+    mapgen_next_state: Option<RunState>,
+    /// How far through the history we are during playback
+    mapgen_index: usize,
+    /// A copy of the map history frames to play
+    mapgen_history: Vec<map_builders::MapSnapshot>,
+    /// Name of the builder that made the history
+    mapgen_builder: &'static str,
+    /// Used for frame timing during playback
+    mapgen_timer: f32,
+    /// How long each frame is shown for, in milliseconds
+    mapgen_delay: f32,
+    /// Whether playback is holding on the current frame
+    mapgen_paused: bool,
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut Rltk) {
+        let mut newrunstate = *(self.ecs.fetch::<RunState>());
+
+        for console in 0..CONSOLE_COUNT {
+            ctx.set_active_console(console);
+            ctx.cls();
+        }
+        ctx.set_active_console(ASCII_CONSOLE);
+        particle_system::cull_dead_particles(&mut self.ecs, ctx.frame_time_ms);
+        // Handle drawing screen based on whether state is in-game or not
+        match newrunstate {
+            // Draw Main Menu screen
+            RunState::MainMenu { .. }
+            | RunState::GameOver { .. }
+            | RunState::ShowOptions { .. }
+            | RunState::ShowHighScores { .. }
+            | RunState::ShowKeyBindings { .. } => {}
+            // Otherwise, handle drawing in-game map
+            _ => {
+                camera::render_camera(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
+            }
+        }
+
+        // Handle updating state based on current state
+        newrunstate = match newrunstate {
+            RunState::MapGeneration => self.mapgen_playback(ctx),
+
+            RunState::PreRun => {
+                self.run_systems();
+                RunState::AwaitingInput
+            }
+
+            RunState::PlayerTurn => self.player_turn(),
+
+            RunState::MonsterTurn => self.monster_turn(),
+
+            RunState::AwaitingInput => player_input(self, ctx),
+
+            RunState::ShowInventory => {
+                let result = gui::show_inventory(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => RunState::ShowInventory,
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let is_ranged = self.ecs.read_storage::<Ranged>();
+                        let is_item_ranged = is_ranged.get(item_entity);
+                        if let Some(ranged_item) = is_item_ranged {
+                            RunState::ShowTargeting {
+                                range: ranged_item.range,
+                                item: item_entity,
+                            }
+                        } else {
+                            let mut intent = self.ecs.write_storage::<WantsToUseItem>();
+                            intent
+                                .insert(
+                                    *self.ecs.fetch::<Entity>(),
+                                    WantsToUseItem {
+                                        item: item_entity,
+                                        target: None,
+                                    },
+                                )
+                                .expect("Unable to insert intent");
+
+                            RunState::PlayerTurn
+                        }
+                    }
+                }
+            }
+
+            RunState::NextLevel => {
+                self.goto_next_level();
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+
+            RunState::ShowDropItem => {
+                let result = gui::drop_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => RunState::ShowDropItem,
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let stack = self
+                            .ecs
+                            .read_storage::<Stackable>()
+                            .get(item_entity)
+                            .map_or(1, |s| s.quantity);
+                        if stack > 1 {
+                            RunState::ShowDropQuantity {
+                                item: item_entity,
+                                quantity: stack,
+                            }
+                        } else {
+                            self.drop_item(item_entity);
+                            RunState::PlayerTurn
+                        }
+                    }
+                }
+            }
+
+            RunState::ShowDropQuantity { item, quantity } => {
+                let (result, quantity) = gui::drop_quantity_menu(self, ctx, item, quantity);
+                match result {
+                    gui::ItemMenuResult::Cancel => RunState::ShowDropItem,
+                    gui::ItemMenuResult::NoResponse => {
+                        RunState::ShowDropQuantity { item, quantity }
+                    }
+                    gui::ItemMenuResult::Selected => {
+                        // Leave the rest of the stack in the backpack
+                        let stack = self
+                            .ecs
+                            .read_storage::<Stackable>()
+                            .get(item)
+                            .map_or(1, |s| s.quantity);
+                        let item = if quantity < stack {
+                            split_stack(&mut self.ecs, item, quantity)
+                        } else {
+                            item
+                        };
+                        self.drop_item(item);
+                        RunState::PlayerTurn
+                    }
+                }
+            }
+
+            RunState::ShowRemoveItem => {
+                let result = gui::remove_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => newrunstate,
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        let mut intent = self.ecs.write_storage::<WantsToRemoveItem>();
+                        intent
+                            .insert(
+                                *self.ecs.fetch::<Entity>(),
+                                WantsToRemoveItem { item: item_entity },
+                            )
+                            .expect("Unable to insert intent");
+                        RunState::PlayerTurn
+                    }
+                }
+            }
+
+            RunState::ShowTargeting { range, item } => {
+                let (action, target) = gui::ranged_target(self, ctx, range);
+                match action {
+                    gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => RunState::ShowTargeting { range, item },
+                    gui::ItemMenuResult::Selected => {
+                        let mut intent = self.ecs.write_storage::<WantsToUseItem>();
+                        intent
+                            .insert(*self.ecs.fetch::<Entity>(), WantsToUseItem { item, target })
+                            .expect("Unable to insert intent");
+
+                        RunState::PlayerTurn
+                    }
+                }
+            }
+
+            RunState::MainMenu { .. } => {
+                let result = gui::main_menu(self, ctx);
+                match result {
+                    gui::MainMenuResult::NoSelection { selected } => RunState::MainMenu {
+                        menu_selection: selected,
+                    },
+                    gui::MainMenuResult::Selected { selected } => match selected {
+                        gui::MainMenuSelection::ResumeGame => RunState::PreRun,
+                        gui::MainMenuSelection::NewGame => {
+                            self.game_over_cleanup();
+                            self.mapgen_next_state = Some(RunState::PreRun);
+                            RunState::MapGeneration
+                        }
+                        gui::MainMenuSelection::SaveGame => RunState::SaveGame,
+                        gui::MainMenuSelection::LoadGame => {
+                            save_load_system::load_game(&mut self.ecs);
+                            save_load_system::delete_save();
+                            RunState::AwaitingInput
+                        }
+                        gui::MainMenuSelection::Options => RunState::ShowOptions {
+                            selection: gui::OptionsSelection::PostEffect,
+                        },
+                        gui::MainMenuSelection::HighScores => RunState::ShowHighScores {
+                            sort: HighScoreSort::Score,
+                            selection: 0,
+                        },
+                        gui::MainMenuSelection::Quit => {
+                            ::std::process::exit(0);
+                        }
+                    },
+                }
+            }
+
+            RunState::ShowOptions { selection } => match gui::options_menu(self, ctx, selection) {
+                gui::OptionsMenuResult::NoSelection { selected } => RunState::ShowOptions {
+                    selection: selected,
+                },
+                gui::OptionsMenuResult::KeyBindings => RunState::ShowKeyBindings {
+                    selection: 0,
+                    capturing: false,
+                    conflict: None,
+                },
+                gui::OptionsMenuResult::Back => RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::Options,
+                },
+            },
+
+            RunState::ShowHighScores { sort, selection } => {
+                match gui::high_scores(self, ctx, sort, selection) {
+                    gui::HighScoresResult::NoResponse { sort, selection } => {
+                        RunState::ShowHighScores { sort, selection }
+                    }
+                    gui::HighScoresResult::Back => RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::HighScores,
+                    },
+                }
+            }
+
+            RunState::ShowShop { vendor, mode } => match gui::shop_menu(self, ctx, vendor, mode) {
+                gui::ShopResult::NoResponse => RunState::ShowShop { vendor, mode },
+                gui::ShopResult::SwitchMode => RunState::ShowShop {
+                    vendor,
+                    mode: match mode {
+                        gui::ShopMode::Buy => gui::ShopMode::Sell,
+                        gui::ShopMode::Sell => gui::ShopMode::Buy,
+                    },
+                },
+                gui::ShopResult::Buy(item) => {
+                    buy_item(&mut self.ecs, vendor, item);
+                    RunState::ShowShop { vendor, mode }
+                }
+                gui::ShopResult::Sell(item) => {
+                    sell_item(&mut self.ecs, vendor, item);
+                    RunState::ShowShop { vendor, mode }
+                }
+                gui::ShopResult::Close => RunState::AwaitingInput,
+            },
+
+            RunState::ShowCrafting => match gui::crafting_menu(self, ctx) {
+                gui::CraftingResult::NoResponse => RunState::ShowCrafting,
+                gui::CraftingResult::Craft(craft) => {
+                    craft_item(&mut self.ecs, &craft);
+                    RunState::PlayerTurn
+                }
+                gui::CraftingResult::Close => RunState::AwaitingInput,
+            },
+
+            RunState::ShowCharacter => match gui::character_sheet(self, ctx) {
+                gui::CharacterSheetResult::NoResponse => RunState::ShowCharacter,
+                gui::CharacterSheetResult::Close => RunState::AwaitingInput,
+            },
+
+            RunState::ShowLook { cursor } => match gui::look_mode(self, ctx, cursor) {
+                gui::LookResult::NoResponse { cursor } => RunState::ShowLook { cursor },
+                gui::LookResult::Close => RunState::AwaitingInput,
+            },
+
+            RunState::ShowHelp { page } => match gui::help_screen(self, ctx, page) {
+                gui::HelpResult::NoResponse { page } => RunState::ShowHelp { page },
+                gui::HelpResult::Close => RunState::AwaitingInput,
+            },
+
+            RunState::ShowKeyBindings {
+                selection,
+                capturing,
+                conflict,
+            } => match gui::key_bindings_menu(self, ctx, selection, capturing, conflict) {
+                gui::KeyBindingsResult::NoResponse {
+                    selection,
+                    capturing,
+                    conflict,
+                } => RunState::ShowKeyBindings {
+                    selection,
+                    capturing,
+                    conflict,
+                },
+                gui::KeyBindingsResult::Back => RunState::ShowOptions {
+                    selection: gui::OptionsSelection::KeyBindings,
+                },
+            },
+
+            RunState::SaveGame => {
+                save_load_system::save_game(&mut self.ecs);
+
+                RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                }
+            }
+
+            RunState::GameOver => {
+                let result = gui::game_over(self, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => newrunstate,
+                    gui::GameOverResult::QuitToMenu => {
+                        self.game_over_cleanup();
+
+                        RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        }
+                    }
+                }
+            }
+
+            RunState::MagicMapReveal { row } => self.reveal_map_row(row),
+
+            #[cfg(feature = "debug-console")]
+            RunState::ShowDebugConsole => debug_console::debug_console(self, ctx),
+
+            #[cfg(feature = "debug-console")]
+            RunState::ShowInspector {
+                entity,
+                show_viewshed,
+                show_path,
+                scroll,
+            } => inspector::inspector(self, ctx, entity, show_viewshed, show_path, scroll),
+        };
+
+        {
+            // Set new runstate
+            let mut runwriter = self.ecs.write_resource::<RunState>();
+            *runwriter = newrunstate;
+        }
+
+        damage_system::delete_the_dead(&mut self.ecs);
+    }
+}
+
+impl State {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> State {
+        State {
+            ecs: World::new(),
+            mapgen_next_state: Some(RunState::MainMenu {
+                menu_selection: gui::MainMenuSelection::NewGame,
+            }),
+            mapgen_index: 0,
+            mapgen_history: Vec::new(),
+            mapgen_builder: "",
+            mapgen_timer: 0.0,
+            mapgen_delay: MAPGEN_FRAME_MS,
+            mapgen_paused: false,
+        }
+    }
+
+    /// Plays back the last map build one snapshot at a time, if the
+    /// visualizer is on, returning the state for the next frame
+    fn mapgen_playback(&mut self, ctx: &mut Rltk) -> RunState {
+        let next_state = self.mapgen_next_state.unwrap();
+        if !self.ecs.fetch::<Settings>().show_mapgen || self.mapgen_history.is_empty() {
+            return next_state;
+        }
+        let last = self.mapgen_history.len() - 1;
+
+        ctx.cls();
+        camera::render_debug_map(&self.mapgen_history[self.mapgen_index].map, ctx);
+        match gui::mapgen_visualizer(self, ctx) {
+            gui::MapgenResult::NoResponse => {}
+            gui::MapgenResult::TogglePause => self.mapgen_paused = !self.mapgen_paused,
+            gui::MapgenResult::StepForward => {
+                self.mapgen_paused = true;
+                self.mapgen_index = usize::min(self.mapgen_index + 1, last);
+            }
+            gui::MapgenResult::StepBack => {
+                self.mapgen_paused = true;
+                self.mapgen_index = self.mapgen_index.saturating_sub(1);
+            }
+            gui::MapgenResult::SpeedUp => {
+                self.mapgen_delay = f32::max(self.mapgen_delay / 2.0, MAPGEN_MIN_FRAME_MS)
+            }
+            gui::MapgenResult::SlowDown => {
+                self.mapgen_delay = f32::min(self.mapgen_delay * 2.0, MAPGEN_MAX_FRAME_MS)
+            }
+            gui::MapgenResult::SkipToEnd => {
+                self.mapgen_paused = true;
+                self.mapgen_index = last;
+            }
+            gui::MapgenResult::Regenerate => {
+                self.regenerate_map();
+                return RunState::MapGeneration;
+            }
+            gui::MapgenResult::Continue => return next_state,
+        }
+
+        if !self.mapgen_paused {
+            self.mapgen_timer += ctx.frame_time_ms;
+            if self.mapgen_timer > self.mapgen_delay {
+                self.mapgen_timer = 0.0;
+                // Once the last frame has had its turn, carry on with the game
+                if self.mapgen_index == last {
+                    return next_state;
+                }
+                self.mapgen_index += 1;
+            }
+        }
+        RunState::MapGeneration
+    }
+
+    /// Has the player drop `item`
+    fn drop_item(&mut self, item: Entity) {
+        let mut intent = self.ecs.write_storage::<WantsToDropItem>();
+        intent
+            .insert(*self.ecs.fetch::<Entity>(), WantsToDropItem { item })
+            .expect("Unable to insert intent");
+    }
+
+    /// Throws away the current level and builds it again from a new seed of
+    /// its own. The run's seed is left alone, so the levels after this one
+    /// come out as they would have
+    fn regenerate_map(&mut self) {
+        let to_delete = self.entities_to_remove_on_level_change();
+        for target in to_delete {
+            self.ecs
+                .delete_entity(target)
+                .expect("Unable to delete entity");
+        }
+
+        let depth = self.ecs.fetch::<Map>().depth;
+        self.build_world_map(depth, new_seed());
+    }
+    /// Counts the turn and lets the systems respond to the player's action
+    fn player_turn(&mut self) -> RunState {
+        {
+            let depth = self.ecs.fetch::<Map>().depth;
+            let mut stats = self.ecs.write_resource::<RunStats>();
+            stats.turns += 1;
+            *stats.turns_per_depth.entry(depth).or_insert(0) += 1;
+        }
+        self.run_systems();
+        self.ecs.maintain();
+        match *self.ecs.fetch::<RunState>() {
+            RunState::MagicMapReveal { .. } => RunState::MagicMapReveal { row: 0 },
+            _ if self.player_hasted() => RunState::AwaitingInput,
+            _ => RunState::MonsterTurn,
+        }
+    }
+
+    /// Whether something the player wears lets them move again before the
+    /// monsters get their turn
+    fn player_hasted(&self) -> bool {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let turns = self.ecs.fetch::<RunStats>().turns;
+        (
+            &self.ecs.read_storage::<Equipped>(),
+            &self.ecs.read_storage::<Speed>(),
+        )
+            .join()
+            .any(|(worn, speed)| worn.owner == player_entity && turns % speed.interval.max(1) == 0)
+    }
+
+    /// Lets the monsters act. A player straining under their load is slow,
+    /// so every other turn the monsters get to act twice
+    fn monster_turn(&mut self) -> RunState {
+        self.run_systems();
+        if self.player_slowed() {
+            damage_system::delete_the_dead(&mut self.ecs);
+            self.run_systems();
+        }
+        RunState::AwaitingInput
+    }
+
+    fn player_slowed(&self) -> bool {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let alive = matches!(
+            self.ecs.read_storage::<CombatStats>().get(player_entity),
+            Some(stats) if stats.hp > 0
+        );
+        let stressed = matches!(
+            self.ecs.read_storage::<Encumbrance>().get(player_entity),
+            Some(encumbrance) if encumbrance.level == EncumbranceLevel::Stressed
+        );
+        alive && stressed && self.ecs.fetch::<RunStats>().turns % 2 == 0
+    }
+
+    /// Reveals one row of the map, returning the state for the next
+    fn reveal_map_row(&mut self, row: i32) -> RunState {
+        let mut map = self.ecs.fetch_mut::<Map>();
+        for x in 0..map.width {
+            let idx = map.xy_idx(x, row);
+            map.revealed_tiles[idx] = true;
+        }
+        if row == map.height - 1 {
+            RunState::MonsterTurn
+        } else {
+            RunState::MagicMapReveal { row: row + 1 }
+        }
+    }
+
+    fn run_systems(&mut self) {
+        // Light has to be known before we can tell what's visible
+        let mut lighting = LightingSystem {};
+        lighting.run_now(&self.ecs);
+        let mut vis = VisibilitySystem {};
+        vis.run_now(&self.ecs);
+        let mut mob_items = MonsterItemSystem {};
+        mob_items.run_now(&self.ecs);
+        let mut mob = MonsterAISystem {};
+        mob.run_now(&self.ecs);
+        // Triggers run after monster ai's update but before we apply
+        // possible damage
+        let mut triggers = TriggerSystem {};
+        triggers.run_now(&self.ecs);
+        let mut periodic_hiding_system = PeriodicHidingSystem {};
+        periodic_hiding_system.run_now(&self.ecs);
+        let mut mapindex = MapIndexingSystem {};
+        mapindex.run_now(&self.ecs);
+        let mut meleecombat = MeleeCombatSystem {};
+        meleecombat.run_now(&self.ecs);
+        let mut damagesystem = DamageSystem {};
+        damagesystem.run_now(&self.ecs);
+        let mut noise = NoiseSystem {};
+        noise.run_now(&self.ecs);
+        let mut encumbrance = EncumbranceSystem {};
+        encumbrance.run_now(&self.ecs);
+        let mut pickup = ItemCollectionSystem {};
+        pickup.run_now(&self.ecs);
+        let mut potions = ItemUseSystem {};
+        potions.run_now(&self.ecs);
+        let mut drop_items = ItemDropSystem {};
+        drop_items.run_now(&self.ecs);
+        let mut item_remove = ItemRemoveSystem {};
+        item_remove.run_now(&self.ecs);
+        let mut hunger_system = HungerSystem {};
+        hunger_system.run_now(&self.ecs);
+        let mut regeneration = RegenerationSystem {};
+        regeneration.run_now(&self.ecs);
+        let mut particles = ParticleSpawnSystem {};
+        particles.run_now(&self.ecs);
+
+        // Forget the oldest messages once the log is longer than the player
+        // wants to keep
+        {
+            let log_length = self.ecs.fetch::<Settings>().log_length;
+            let mut log = self.ecs.write_resource::<GameLog>();
+            if log.entries.len() > log_length {
+                let excess = log.entries.len() - log_length;
+                log.entries.drain(..excess);
+            }
+        }
+
+        self.ecs.maintain();
+    }
+
+    /// Builds the level at `new_depth`, seeded from the run's seed so the
+    /// same seed always makes the same levels
+    pub fn generate_world_map(&mut self, new_depth: i32) {
+        let seed = map_builders::level_seed(self.ecs.fetch::<RunStats>().seed, new_depth);
+        self.build_world_map(new_depth, seed);
+    }
+
+    fn build_world_map(&mut self, new_depth: i32, seed: u64) {
+        // Reset Map Gen variables
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        self.mapgen_history.clear();
+        self.mapgen_paused = false;
+
+        // Create a new map
+        let mut builder = map_builders::random_builder(new_depth, seed);
+        if self.ecs.fetch::<Settings>().show_mapgen {
+            builder.record_snapshot_history();
+        }
+        builder.build_map();
+        self.mapgen_history = builder.get_snapshot_history();
+        self.mapgen_builder = builder.get_name();
+
+        // Apply new map to World's Map resource
+        {
+            let mut worldmap_resource = self.ecs.write_resource::<Map>();
+            *worldmap_resource = builder.get_map();
+        }
+
+        // Spawn bad guys
+        builder.spawn_entities(&mut self.ecs);
+
+        // Place the player and update resources
+        let player_start = builder.get_starting_position();
+        let (player_x, player_y) = (player_start.x, player_start.y);
+        let mut player_position = self.ecs.write_resource::<Point>();
+        *player_position = Point::new(player_x, player_y);
+        let mut position_components = self.ecs.write_storage::<Position>();
+        let player_entity = self.ecs.fetch::<Entity>();
+        let player_pos_comp = position_components.get_mut(*player_entity);
+        if let Some(player_pos_comp) = player_pos_comp {
+            player_pos_comp.x = player_x;
+            player_pos_comp.y = player_y;
+        }
+
+        // Mark the player's visibility as dirty
+        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
+        let vs = viewshed_components.get_mut(*player_entity);
+        if let Some(vs) = vs {
+            vs.dirty = true;
+        }
+    }
+
+    /// Inserts the resources a new game needs, with its dice seeded from
+    /// `seed`
+    pub fn init_resources(&mut self, seed: u64) {
+        let player_entity = spawner::player(&mut self.ecs, 0, 0);
+
+        let (width, height) = map_builders::map_dimensions(1);
+        self.ecs.insert(Map::new(1, width, height));
+        self.ecs.insert(Point::new(0, 0));
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+        self.ecs.insert(player_entity);
+        self.ecs.insert(particle_system::ParticleBuilder::new());
+        self.ecs.insert(noise_system::NoiseBuilder::new());
+        self.ecs.insert(rex_assets::RexAssets::new());
+        self.ecs.insert(faction_table::default_faction_table());
+        self.ecs.insert(crafting::load_recipes());
+        self.ecs.insert(tileset::default_tileset());
+        self.ecs.insert(RenderMode::Ascii);
+        self.ecs.insert(RunStats::new(seed));
+        self.ecs.insert(ItemIdentities::new(seed));
+        self.ecs.insert(RunState::MapGeneration {});
+        #[cfg(feature = "debug-console")]
+        self.ecs.insert(DebugConsole::default());
+        self.ecs.insert(GameLog {
+            entries: vec!["Welcome to Roguie!".to_string()],
+        });
+    }
+
+    /// Returns a vec of all Entities to delete. This includes non-players, and
+    /// non-player-owned entities
+    fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
+        let entities = self.ecs.entities();
+        let player = self.ecs.read_storage::<Player>();
+        let backpack = self.ecs.read_storage::<InBackpack>();
+        let player_entity = self.ecs.fetch::<Entity>();
+        let equipped = self.ecs.read_storage::<Equipped>();
+
+        let mut to_delete: Vec<Entity> = Vec::new();
+        for entity in entities.join() {
+            let mut should_delete = true;
+
+            // Make sure not to delete player
+            let p = player.get(entity);
+            if let Some(_) = p {
+                should_delete = false;
+            }
+
+            // Don't delete player's equipment
+            let bp = backpack.get(entity);
+            if let Some(bp) = bp {
+                if bp.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
+
+            let eq = equipped.get(entity);
+            if let Some(eq) = eq {
+                if eq.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
+
+            if should_delete {
+                to_delete.push(entity);
+            }
+        }
+        to_delete
+    }
+
+    fn goto_next_level(&mut self) {
+        // Delete entities that aren't the player or his/her equipment
+        let to_delete = self.entities_to_remove_on_level_change();
+        for target in to_delete {
+            self.ecs
+                .delete_entity(target)
+                .expect("Unable to delete entity");
+        }
+
+        // Build a new map and place the player
+        let current_depth;
+        {
+            let worldmap_resource = self.ecs.fetch::<Map>();
+            current_depth = worldmap_resource.depth;
+        }
+        self.generate_world_map(current_depth + 1);
+
+        // Notify the player and give them some health
+        let player_entity = self.ecs.fetch::<Entity>();
+        let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
+        gamelog
+            .entries
+            .push("You descend to the next level, and take a moment to heal.".to_string());
+        let mut player_health_store = self.ecs.write_storage::<CombatStats>();
+        let player_health = player_health_store.get_mut(*player_entity);
+        if let Some(player_health) = player_health {
+            player_health.hp = i32::max(player_health.hp, player_health.max_hp / 2);
+        }
+    }
+
+    fn game_over_cleanup(&mut self) {
+        // Delete everything
+        let mut to_delete = Vec::new();
+        for e in self.ecs.entities().join() {
+            to_delete.push(e);
+        }
+        for del in to_delete.iter() {
+            self.ecs.delete_entity(*del).expect("Deletion failed");
+        }
+
+        // Spawn a new player
+        {
+            let player_entity = spawner::player(&mut self.ecs, 0, 0);
+            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
+            *player_entity_writer = player_entity;
+        }
+
+        // Start counting afresh, from a new seed
+        let seed = new_seed();
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+        self.ecs.insert(RunStats::new(seed));
+        self.ecs.insert(ItemIdentities::new(seed));
+        self.ecs.remove::<Morgue>();
+
+        // Build a new map and place the player
+        self.generate_world_map(1);
+    }
+}
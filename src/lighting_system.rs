@@ -0,0 +1,60 @@
+use super::{LightSource, Map, Position};
+use rltk::{field_of_view, Point, RGB};
+use specs::prelude::*;
+
+/// How bright a tile must be before the player can see what is on it
+pub const MIN_VISIBLE_LIGHT: f32 = 0.15;
+
+/// Recalculates the light level of every tile from the map's ambient light and
+/// every `LightSource` in the world
+pub struct LightingSystem {}
+
+impl<'a> System<'a> for LightingSystem {
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, LightSource>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut map, positions, lights) = data;
+
+        let ambient = RGB::from_f32(map.ambient_light, map.ambient_light, map.ambient_light);
+        for light in map.light.iter_mut() {
+            *light = ambient;
+        }
+
+        for (pos, light) in (&positions, &lights).join() {
+            let origin = Point::new(pos.x, pos.y);
+            let range = light.range as f32;
+            let lit_tiles = field_of_view(origin, light.range, &*map);
+
+            for tile in lit_tiles.iter() {
+                if tile.x < 0 || tile.x >= map.width || tile.y < 0 || tile.y >= map.height {
+                    continue;
+                }
+                let idx = map.xy_idx(tile.x, tile.y);
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(origin, *tile);
+                // Light fades out linearly towards the edge of its range
+                let intensity = f32::max(0.0, (range - distance) / range);
+                let lit = map.light[idx] + light.color * intensity;
+                map.light[idx] = RGB::from_f32(
+                    f32::min(1.0, lit.r),
+                    f32::min(1.0, lit.g),
+                    f32::min(1.0, lit.b),
+                );
+            }
+        }
+    }
+}
+
+/// Tints `color` by the `light` falling on it, keeping a little of the
+/// original color so lit-by-proximity tiles don't vanish entirely
+pub fn apply_light(color: RGB, light: RGB) -> RGB {
+    let tint = RGB::from_f32(
+        0.25 + light.r * 0.75,
+        0.25 + light.g * 0.75,
+        0.25 + light.b * 0.75,
+    );
+    color * tint
+}
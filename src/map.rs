@@ -1,13 +1,10 @@
-use rltk::{Algorithm2D, BaseMap, Point, Rltk, SmallVec, RGB};
+use super::{apply_light, MIN_VISIBLE_LIGHT};
+use rltk::{Algorithm2D, BaseMap, Point, SmallVec, RGB};
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use std::collections::HashSet;
 
-pub const MAP_WIDTH: usize = 80;
-pub const MAP_HEIGHT: usize = 43;
-pub const MAP_COUNT: usize = MAP_HEIGHT * MAP_WIDTH;
-
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
@@ -24,32 +21,63 @@ pub struct Map {
     pub blocked: Vec<bool>,
     pub depth: i32,
     pub bloodstains: HashSet<usize>,
+    /// Brightness of light that reaches every tile without a light source
+    pub ambient_light: f32,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
+
+    /// Color and brightness of the light falling on each tile, rebuilt by
+    /// `LightingSystem` every turn
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub light: Vec<RGB>,
 }
 
 impl Map {
-    /// Generates an empty map, consisting entirely of solid walls
-    pub fn new(new_depth: i32) -> Map {
+    /// Generates an empty `width` by `height` map, consisting entirely of
+    /// solid walls
+    pub fn new(new_depth: i32, width: i32, height: i32) -> Map {
+        let map_count = (width * height) as usize;
         Map {
-            tiles: vec![TileType::Wall; MAP_COUNT],
-            width: MAP_WIDTH as i32,
-            height: MAP_HEIGHT as i32,
-            revealed_tiles: vec![false; MAP_COUNT],
-            visible_tiles: vec![false; MAP_COUNT],
-            blocked: vec![false; MAP_COUNT],
-            tile_content: vec![Vec::new(); MAP_COUNT],
+            tiles: vec![TileType::Wall; map_count],
+            width,
+            height,
+            revealed_tiles: vec![false; map_count],
+            visible_tiles: vec![false; map_count],
+            blocked: vec![false; map_count],
+            tile_content: vec![Vec::new(); map_count],
             depth: new_depth,
             bloodstains: HashSet::new(),
+            ambient_light: ambient_light_for_depth(new_depth),
+            light: vec![RGB::from_f32(0., 0., 0.); map_count],
         }
     }
 
+    /// Whether enough light falls on tile `idx` to see by
+    pub fn is_lit(&self, idx: usize) -> bool {
+        let light = self.light[idx];
+        f32::max(light.r, f32::max(light.g, light.b)) >= MIN_VISIBLE_LIGHT
+    }
+
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         (y as usize * self.width as usize) + x as usize
     }
 
+    /// Whether `x, y` lies within the map
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    /// Rebuilds the tile indices that aren't saved with the map, after
+    /// loading one
+    pub fn rebuild_indices(&mut self) {
+        let map_count = (self.width * self.height) as usize;
+        self.tile_content = vec![Vec::new(); map_count];
+        self.light = vec![RGB::from_f32(0., 0., 0.); map_count];
+    }
+
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
             self.blocked[i] = *tile == TileType::Wall
@@ -71,6 +99,11 @@ impl Map {
     }
 }
 
+/// The deeper you go the darker it gets, until only light sources help
+fn ambient_light_for_depth(depth: i32) -> f32 {
+    f32::max(0.0, 0.7 - 0.1 * (depth - 1) as f32)
+}
+
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
         self.tiles[idx] == TileType::Wall
@@ -127,57 +160,83 @@ impl Algorithm2D for Map {
     }
 }
 
-pub fn draw_map(map: &Map, ctx: &mut Rltk) {
-    let mut y = 0;
-    let mut x = 0;
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        // Render a tile depending upon the tile type
-
-        if map.revealed_tiles[idx] {
-            let glyph;
-            let mut fg;
-            let mut bg = RGB::from_f32(0., 0., 0.);
-
-            match tile {
-                TileType::Floor => {
-                    glyph = rltk::to_cp437('.');
-                    fg = RGB::from_f32(0.0, 0.5, 0.5);
-                }
-                TileType::Wall => {
-                    glyph = wall_glyph(&*map, x, y);
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-                TileType::DownStairs => {
-                    glyph = rltk::to_cp437('⌂');
-                    fg = RGB::from_f32(0., 1.0, 1.0);
-                }
-            }
-
-            if map.bloodstains.contains(&idx) {
-                bg = RGB::from_f32(0.75, 0., 0.);
-            }
-
-            if !map.visible_tiles[idx] {
-                fg = fg.to_greyscale();
-                bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
-            }
-
-            ctx.set(x, y, fg, bg, glyph);
-        }
+/// Returns the glyph, foreground and background colors to draw the tile at
+/// `idx` with, assuming it has been revealed
+pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
+    let x = idx as i32 % map.width;
+    let y = idx as i32 / map.width;
+    let (mut glyph, mut fg) = tile_appearance(map.tiles[idx]);
+    let mut bg = RGB::from_f32(0., 0., 0.);
 
-        // Move the coordinates
-        x += 1;
-        if x > 79 {
-            x = 0;
-            y += 1;
-        }
+    if map.tiles[idx] == TileType::Wall {
+        glyph = wall_glyph(&*map, x, y);
+    }
+
+    if map.bloodstains.contains(&idx) {
+        bg = bloodstain_color();
+    }
+
+    if map.visible_tiles[idx] {
+        fg = apply_light(fg, map.light[idx]);
+        bg = apply_light(bg, map.light[idx]);
+    } else {
+        fg = fg.to_greyscale();
+        bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
     }
+
+    (glyph, fg, bg)
+}
+
+/// The glyph and colour `tile` is drawn with before lighting. Walls join up
+/// with their neighbours on the map, so for them this is a plain stretch
+pub fn tile_appearance(tile: TileType) -> (rltk::FontCharType, RGB) {
+    match tile {
+        TileType::Floor => (rltk::to_cp437('.'), RGB::from_f32(0.0, 0.5, 0.5)),
+        TileType::Wall => (205, RGB::from_f32(0., 1.0, 0.)),
+        TileType::DownStairs => (rltk::to_cp437('⌂'), RGB::from_f32(0., 1.0, 1.0)),
+    }
+}
+
+/// The background of a tile someone has bled on
+pub fn bloodstain_color() -> RGB {
+    RGB::from_f32(0.75, 0., 0.)
 }
 
 fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
     // If at map edges, return a simple wall
+    let mask = match wall_mask(map, x, y) {
+        Some(mask) => mask,
+        None => return 35,
+    };
+
+    // Return the appropriate line-drawing character from the codepage 437
+    // character set: https://dwarffortresswiki.org/index.php/Character_table
+    match mask {
+        0 => 8,    // Pillar because we can't see neighbors
+        1 => 186,  // Wall only to the north
+        2 => 186,  // Wall only to the south
+        3 => 186,  // Wall to the north and south
+        4 => 205,  // Wall only to the west
+        5 => 188,  // Wall to the north and west
+        6 => 187,  // Wall to the south and west
+        7 => 185,  // Wall to the north, south and west
+        8 => 205,  // Wall only to the east
+        9 => 200,  // Wall to the north and east
+        10 => 201, // Wall to the south and east
+        11 => 204, // Wall to the north, south and east
+        12 => 205, // Wall to the east and west
+        13 => 202, // Wall to the east, west, and south
+        14 => 203, // Wall to the east, west, and north
+        15 => 206, // ╬ Wall on all sides
+        _ => 35,   // We missed one?
+    }
+}
+
+/// Returns which of the wall at `x, y`'s orthogonal neighbors are also walls,
+/// or `None` for walls on the edge of the map
+pub fn wall_mask(map: &Map, x: i32, y: i32) -> Option<u8> {
     if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 as i32 {
-        return 35;
+        return None;
     }
 
     // Calculate what type of wall to display based on neighbors
@@ -202,27 +261,7 @@ fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
         mask += 8;
     }
 
-    // Return the appropriate line-drawing character from the codepage 437
-    // character set: https://dwarffortresswiki.org/index.php/Character_table
-    match mask {
-        0 => 8,    // Pillar because we can't see neighbors
-        1 => 186,  // Wall only to the north
-        2 => 186,  // Wall only to the south
-        3 => 186,  // Wall to the north and south
-        4 => 205,  // Wall only to the west
-        5 => 188,  // Wall to the north and west
-        6 => 187,  // Wall to the south and west
-        7 => 185,  // Wall to the north, south and west
-        8 => 205,  // Wall only to the east
-        9 => 200,  // Wall to the north and east
-        10 => 201, // Wall to the south and east
-        11 => 204, // Wall to the north, south and east
-        12 => 205, // Wall to the east and west
-        13 => 202, // Wall to the east, west, and south
-        14 => 203, // Wall to the east, west, and north
-        15 => 206, // ╬ Wall on all sides
-        _ => 35,   // We missed one?
-    }
+    Some(mask)
 }
 
 // fn is_revealed_and_wall(map: &Map, x: i32, y: i32) -> bool {
@@ -1,7 +1,5 @@
 use super::common::*;
-use crate::{
-    map_builders::MapBuilder, spawner, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER,
-};
+use crate::{map_builders::MapBuilder, map_builders::MapSnapshot, Map, Position, Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 /// Builds a Dungeon Map using Binary Space Partitioning
@@ -9,19 +7,25 @@ pub struct BspDungeonBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
+    /// Seeds the generator, so the same seed always builds the same map
+    seed: u64,
     rooms: Vec<Rect>,
-    history: Vec<Map>,
+    history: Vec<MapSnapshot>,
+    /// Whether `take_snapshot` keeps anything
+    record_history: bool,
     rects: Vec<Rect>,
 }
 
 impl BspDungeonBuilder {
-    pub fn new(new_depth: i32) -> BspDungeonBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, seed: u64) -> BspDungeonBuilder {
         BspDungeonBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
+            seed,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
             rects: Vec::new(),
         }
     }
@@ -158,7 +162,7 @@ impl BspDungeonBuilder {
     }
 
     fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
+        let mut rng = RandomNumberGenerator::seeded(self.seed);
         // Clear old room rects
         self.rects.clear();
         // Start with a single map-sized rectangle
@@ -183,7 +187,7 @@ impl BspDungeonBuilder {
                 // Sub-divide it
                 self.add_subrects(rect);
                 // Save a snapshot to history
-                self.take_snapshot();
+                self.take_snapshot(&format!("Placed room {}", self.rooms.len()));
             }
             n_rooms += 1;
         }
@@ -201,7 +205,7 @@ impl BspDungeonBuilder {
             let end_y =
                 next_room.y1 + (rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2)) - 1);
             self.draw_corridor(start_x, start_y, end_x, end_y);
-            self.take_snapshot();
+            self.take_snapshot(&format!("Joined room {} to room {}", i + 1, i + 2));
         }
 
         // Find player starting position
@@ -219,6 +223,10 @@ impl BspDungeonBuilder {
 }
 
 impl MapBuilder for BspDungeonBuilder {
+    fn get_name(&self) -> &'static str {
+        "BSP Dungeon"
+    }
+
     fn get_map(&self) -> Map {
         self.map.clone()
     }
@@ -227,7 +235,11 @@ impl MapBuilder for BspDungeonBuilder {
         self.starting_position.clone()
     }
 
-    fn get_snapshot_history(&self) -> Vec<Map> {
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<MapSnapshot> {
         self.history.clone()
     }
 
@@ -236,18 +248,23 @@ impl MapBuilder for BspDungeonBuilder {
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
-        for room in self.rooms.iter().skip(1) {
-            spawner::fill_room(ecs, room, self.depth);
-        }
+        spawn_rooms(ecs, &self.rooms, self.depth);
+    }
+
+    fn record_snapshot_history(&mut self) {
+        self.record_history = true;
     }
 
-    fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+    fn take_snapshot(&mut self, description: &str) {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
             }
-            self.history.push(snapshot);
+            self.history.push(MapSnapshot {
+                map: snapshot,
+                description: description.to_string(),
+            });
         }
     }
 }
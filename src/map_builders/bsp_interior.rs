@@ -1,5 +1,6 @@
-use super::{MapBuilder, MIN_ROOM_SIZE};
-use crate::{spawner, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use super::common::spawn_rooms;
+use super::{MapBuilder, MapSnapshot, MIN_ROOM_SIZE};
+use crate::{Map, Position, Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
@@ -7,12 +8,20 @@ pub struct BspInteriorBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
+    /// Seeds the generator, so the same seed always builds the same map
+    seed: u64,
     rooms: Vec<Rect>,
-    history: Vec<Map>,
+    history: Vec<MapSnapshot>,
+    /// Whether `take_snapshot` keeps anything
+    record_history: bool,
     rects: Vec<Rect>,
 }
 
 impl MapBuilder for BspInteriorBuilder {
+    fn get_name(&self) -> &'static str {
+        "BSP Interior"
+    }
+
     fn get_map(&self) -> Map {
         self.map.clone()
     }
@@ -21,7 +30,11 @@ impl MapBuilder for BspInteriorBuilder {
         self.starting_position.clone()
     }
 
-    fn get_snapshot_history(&self) -> Vec<Map> {
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<MapSnapshot> {
         self.history.clone()
     }
 
@@ -30,36 +43,43 @@ impl MapBuilder for BspInteriorBuilder {
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
-        for room in self.rooms.iter().skip(1) {
-            spawner::fill_room(ecs, room, self.depth);
-        }
+        spawn_rooms(ecs, &self.rooms, self.depth);
+    }
+
+    fn record_snapshot_history(&mut self) {
+        self.record_history = true;
     }
 
-    fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+    fn take_snapshot(&mut self, description: &str) {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
             }
-            self.history.push(snapshot);
+            self.history.push(MapSnapshot {
+                map: snapshot,
+                description: description.to_string(),
+            });
         }
     }
 }
 
 impl BspInteriorBuilder {
-    pub fn new(new_depth: i32) -> BspInteriorBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, seed: u64) -> BspInteriorBuilder {
         BspInteriorBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
+            seed,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
             rects: Vec::new(),
         }
     }
 
     fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
+        let mut rng = RandomNumberGenerator::seeded(self.seed);
         // Clear old rooms
         self.rects.clear();
         // Start with a single map-sized rectangle
@@ -83,7 +103,7 @@ impl BspInteriorBuilder {
                     }
                 }
             }
-            self.take_snapshot();
+            self.take_snapshot(&format!("Carved room {}", self.rooms.len()));
         }
 
         let start = self.rooms[0].center();
@@ -103,7 +123,7 @@ impl BspInteriorBuilder {
             let end_y =
                 next_room.y1 + (rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2)) - 1);
             self.draw_corridor(start_x, start_y, end_x, end_y);
-            self.take_snapshot();
+            self.take_snapshot(&format!("Joined room {} to room {}", i + 1, i + 2));
         }
 
         // Don't forget the stairs
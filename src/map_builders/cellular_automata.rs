@@ -1,17 +1,25 @@
-use super::{spawner, Map, MapBuilder, Position, TileType, SHOW_MAPGEN_VISUALIZER};
+use super::{spawner, Map, MapBuilder, MapSnapshot, Position, Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub struct CellularAutomataBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
-    history: Vec<Map>,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    /// Seeds the generator, so the same seed always builds the same map
+    seed: u64,
+    history: Vec<MapSnapshot>,
+    /// Whether `take_snapshot` keeps anything
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl MapBuilder for CellularAutomataBuilder {
+    fn get_name(&self) -> &'static str {
+        "Cellular Automata"
+    }
+
     fn get_map(&self) -> Map {
         self.map.clone()
     }
@@ -20,7 +28,11 @@ impl MapBuilder for CellularAutomataBuilder {
         self.starting_position.clone()
     }
 
-    fn get_snapshot_history(&self) -> Vec<Map> {
+    fn get_rooms(&self) -> Vec<Rect> {
+        Vec::new()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<MapSnapshot> {
         self.history.clone()
     }
 
@@ -34,30 +46,43 @@ impl MapBuilder for CellularAutomataBuilder {
         }
     }
 
-    fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+    fn record_snapshot_history(&mut self) {
+        self.record_history = true;
+    }
+
+    fn take_snapshot(&mut self, description: &str) {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
             }
-            self.history.push(snapshot);
+            self.history.push(MapSnapshot {
+                map: snapshot,
+                description: description.to_string(),
+            });
         }
     }
 }
 
 impl CellularAutomataBuilder {
-    pub fn new(new_depth: i32) -> CellularAutomataBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, seed: u64) -> CellularAutomataBuilder {
+        // Caves are pitch black, only light sources will help you here
+        let mut map = Map::new(new_depth, width, height);
+        map.ambient_light = 0.0;
+
         CellularAutomataBuilder {
-            map: Map::new(new_depth),
+            map,
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
+            seed,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
         }
     }
 
     fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
+        let mut rng = RandomNumberGenerator::seeded(self.seed);
 
         // Completely randomize the map, setting 55% of it to be floor.
         for y in 1..self.map.height - 1 {
@@ -71,10 +96,10 @@ impl CellularAutomataBuilder {
                 }
             }
         }
-        self.take_snapshot();
+        self.take_snapshot("Filled with random noise");
 
         // Iteratively apply cellular automata rules
-        for _i in 0..15 {
+        for i in 0..15 {
             let mut newtiles = self.map.tiles.clone();
 
             // For every tile
@@ -93,7 +118,7 @@ impl CellularAutomataBuilder {
             }
 
             self.map.tiles = newtiles.clone();
-            self.take_snapshot();
+            self.take_snapshot(&format!("Smoothing pass {} of 15", i + 1));
         }
 
         // Clear out lonely pillars
@@ -109,7 +134,7 @@ impl CellularAutomataBuilder {
             }
         }
         self.map.tiles = newtiles.clone();
-        self.take_snapshot();
+        self.take_snapshot("Cleared out lonely pillars");
 
         // Find a starting point
         // Start at the middle and
@@ -158,10 +183,10 @@ impl CellularAutomataBuilder {
                 }
             }
         }
-        self.take_snapshot();
+        self.take_snapshot("Walled off unreachable areas");
 
         self.map.tiles[exit_tile.0] = TileType::DownStairs;
-        self.take_snapshot();
+        self.take_snapshot("Placed the stairs");
 
         // Build a noise map for use in spawning entities later
         // Construct a Cellular Noise seed
@@ -1,6 +1,28 @@
-use super::{Map, Rect, TileType};
+use super::{spawner, Map, Rect, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
 use std::cmp::{max, min};
 
+/// Fills every room but the first, where the player starts, with monsters
+/// and items. On some levels one of them is a shop instead
+pub fn spawn_rooms(ecs: &mut World, rooms: &[Rect], depth: i32) {
+    let shop = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        if rooms.len() > 2 && rng.roll_dice(1, 3) == 1 {
+            Some(rng.range(1, rooms.len() as i32) as usize)
+        } else {
+            None
+        }
+    };
+    for (i, room) in rooms.iter().enumerate().skip(1) {
+        if shop == Some(i) {
+            spawner::shop_room(ecs, room);
+        } else {
+            spawner::fill_room(ecs, room, depth);
+        }
+    }
+}
+
 pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
     for y in room.y1 + 1..=room.y2 {
         for x in room.x1 + 1..=room.x2 {
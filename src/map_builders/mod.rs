@@ -13,23 +13,78 @@ use cellular_automata::*;
 use simple_map::*;
 
 const MIN_ROOM_SIZE: i32 = 8;
+/// Levels grow with depth, up to this size
+const MAX_MAP_WIDTH: i32 = 160;
+const MAX_MAP_HEIGHT: i32 = 100;
+
+/// The map as it stood after one step of a build, for the visualizer
+#[derive(Clone)]
+pub struct MapSnapshot {
+    pub map: Map,
+    /// What the step did
+    pub description: String,
+}
 
 pub trait MapBuilder {
+    /// The builder's name, as the visualizer shows it
+    fn get_name(&self) -> &'static str;
     fn build_map(&mut self);
     fn spawn_entities(&mut self, ecs: &mut World);
     fn get_map(&self) -> Map;
     fn get_starting_position(&self) -> Position;
-    fn get_snapshot_history(&self) -> Vec<Map>;
-    fn take_snapshot(&mut self);
+    /// The rooms the map was carved from, if it has any
+    fn get_rooms(&self) -> Vec<Rect>;
+    fn get_snapshot_history(&self) -> Vec<MapSnapshot>;
+    /// Keeps a snapshot of every step of the build, for the visualizer
+    fn record_snapshot_history(&mut self);
+    fn take_snapshot(&mut self, description: &str);
+}
+
+/// Names `named_builder` accepts, one per builder
+pub const BUILDER_NAMES: [&str; 4] = ["bsp_dungeon", "bsp_interior", "simple", "cellular"];
+
+/// Returns the width and height of the map for a level at `depth`
+pub fn map_dimensions(depth: i32) -> (i32, i32) {
+    let width = i32::min(MAX_MAP_WIDTH, 80 + 20 * (depth - 1));
+    let height = i32::min(MAX_MAP_HEIGHT, 43 + 10 * (depth - 1));
+    (width, height)
+}
+
+/// Returns the seed for the level at `depth` in a game started from `seed`,
+/// so each level differs but a game's levels can be built again
+pub fn level_seed(seed: u64, depth: i32) -> u64 {
+    seed.wrapping_add((depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Returns the builder called `name` for a level at `depth`, or `None` if
+/// there's no builder by that name
+pub fn named_builder(name: &str, new_depth: i32, seed: u64) -> Option<Box<dyn MapBuilder>> {
+    let (width, height) = map_dimensions(new_depth);
+    match name {
+        "bsp_dungeon" => Some(Box::new(BspDungeonBuilder::new(
+            new_depth, width, height, seed,
+        ))),
+        "bsp_interior" => Some(Box::new(BspInteriorBuilder::new(
+            new_depth, width, height, seed,
+        ))),
+        "simple" => Some(Box::new(SimpleMapBuilder::new(
+            new_depth, width, height, seed,
+        ))),
+        "cellular" => Some(Box::new(CellularAutomataBuilder::new(
+            new_depth, width, height, seed,
+        ))),
+        _ => None,
+    }
 }
 
-pub fn random_builder(new_depth: i32) -> Box<dyn MapBuilder> {
-    let mut rng = rltk::RandomNumberGenerator::new();
+pub fn random_builder(new_depth: i32, seed: u64) -> Box<dyn MapBuilder> {
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
     let builder = rng.roll_dice(1, 7);
+    let (width, height) = map_dimensions(new_depth);
     match builder {
-        1 => Box::new(BspDungeonBuilder::new(new_depth)),
-        2 => Box::new(BspInteriorBuilder::new(new_depth)),
-        3 => Box::new(SimpleMapBuilder::new(new_depth)),
-        _ => Box::new(CellularAutomataBuilder::new(new_depth)),
+        1 => Box::new(BspDungeonBuilder::new(new_depth, width, height, seed)),
+        2 => Box::new(BspInteriorBuilder::new(new_depth, width, height, seed)),
+        3 => Box::new(SimpleMapBuilder::new(new_depth, width, height, seed)),
+        _ => Box::new(CellularAutomataBuilder::new(new_depth, width, height, seed)),
     }
 }
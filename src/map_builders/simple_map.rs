@@ -1,9 +1,9 @@
 use super::common::*;
-use super::spawner;
 use super::Map;
 use super::MapBuilder;
+use super::MapSnapshot;
 use super::Position;
-use crate::{Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::{Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
@@ -11,11 +11,19 @@ pub struct SimpleMapBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
+    /// Seeds the generator, so the same seed always builds the same map
+    seed: u64,
     rooms: Vec<Rect>,
-    history: Vec<Map>,
+    history: Vec<MapSnapshot>,
+    /// Whether `take_snapshot` keeps anything
+    record_history: bool,
 }
 
 impl MapBuilder for SimpleMapBuilder {
+    fn get_name(&self) -> &'static str {
+        "Simple Map"
+    }
+
     fn get_map(&self) -> Map {
         self.map.clone()
     }
@@ -29,35 +37,46 @@ impl MapBuilder for SimpleMapBuilder {
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
-        for room in self.rooms.iter().skip(1) {
-            spawner::fill_room(ecs, room, self.depth);
-        }
+        spawn_rooms(ecs, &self.rooms, self.depth);
     }
 
-    fn get_snapshot_history(&self) -> Vec<Map> {
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<MapSnapshot> {
         self.history.clone()
     }
 
-    fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+    fn record_snapshot_history(&mut self) {
+        self.record_history = true;
+    }
+
+    fn take_snapshot(&mut self, description: &str) {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             // Reveal all tiles before saving the map
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
             }
-            self.history.push(snapshot);
+            self.history.push(MapSnapshot {
+                map: snapshot,
+                description: description.to_string(),
+            });
         }
     }
 }
 
 impl SimpleMapBuilder {
-    pub fn new(new_depth: i32) -> SimpleMapBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32, seed: u64) -> SimpleMapBuilder {
         SimpleMapBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
+            seed,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
         }
     }
 
@@ -66,7 +85,7 @@ impl SimpleMapBuilder {
         const MIN_SIZE: i32 = 6;
         const MAX_SIZE: i32 = 10;
 
-        let mut rng = RandomNumberGenerator::new();
+        let mut rng = RandomNumberGenerator::seeded(self.seed);
 
         for i in 0..MAX_ROOMS {
             let w = rng.range(MIN_SIZE, MAX_SIZE);
@@ -83,7 +102,7 @@ impl SimpleMapBuilder {
             if ok {
                 apply_room_to_map(&mut self.map, &new_room);
 
-                self.take_snapshot();
+                self.take_snapshot(&format!("Placed room {}", self.rooms.len() + 1));
 
                 if !self.rooms.is_empty() {
                     let (new_x, new_y) = new_room.center();
@@ -95,10 +114,10 @@ impl SimpleMapBuilder {
                         apply_vertical_tunnel(&mut self.map, prev_y, new_y, prev_x);
                         apply_horizontal_tunnel(&mut self.map, prev_x, new_x, new_y);
                     }
+                    self.take_snapshot(&format!("Dug a tunnel to room {}", self.rooms.len() + 1));
                 }
 
                 self.rooms.push(new_room);
-                self.take_snapshot();
             }
         }
 
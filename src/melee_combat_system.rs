@@ -1,12 +1,17 @@
 use super::{
-    gamelog::GameLog, particle_system::ParticleBuilder, CombatStats, DefenseBonus, Equipped,
-    HungerClock, HungerState, MeleePowerBonus, Name, Position, SufferDamage, WantsToMelee,
+    gamelog::GameLog, particle_system::ParticleBuilder, Awareness, AwarenessState, CombatStats,
+    DamageSource, DefenseBonus, EquipmentSlot, Equipped, HungerClock, HungerState, Map,
+    MeleePowerBonus, Name, NoiseBuilder, Position, SufferDamage, WantsToMelee,
 };
 use specs::prelude::*;
 
+/// How much weaker a strike with the off hand is than one with the main hand
+const OFF_HAND_PENALTY: i32 = 2;
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         Entities<'a>,
         WriteExpect<'a, GameLog>,
@@ -20,6 +25,10 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, HungerClock>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, Awareness>,
+        WriteExpect<'a, NoiseBuilder>,
+        ReadExpect<'a, Entity>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -36,6 +45,10 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut particle_builder,
             positions,
             hunger_clocks,
+            map,
+            mut awareness,
+            mut noise,
+            player_entity,
         ) = data;
         for (entity, wants_melee, name, stats) in
             (&entities, &wants_melee, &names, &combat_stats).join()
@@ -44,15 +57,21 @@ impl<'a> System<'a> for MeleeCombatSystem {
             if stats.hp > 0 {
                 // Add any melee powe bonuses
                 let mut offensive_bonus = 0;
+                let mut off_hand_bonus = None;
 
                 for (_item_entity, power_bonus, equipped_by) in
                     (&entities, &melee_power_bonuses, &equipped).join()
                 // .filter(|b| b.2.owner == entity)
                 {
                     // Find any equipped items that give a melee power bonus w/
-                    // and owner of this entity
+                    // and owner of this entity. A weapon in the off hand
+                    // strikes on its own instead
                     if equipped_by.owner == entity {
-                        offensive_bonus += power_bonus.power;
+                        if equipped_by.slot == EquipmentSlot::Shield {
+                            off_hand_bonus = Some(power_bonus.power - OFF_HAND_PENALTY);
+                        } else {
+                            offensive_bonus += power_bonus.power;
+                        }
                     }
                 }
 
@@ -60,6 +79,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 if let Some(hc) = hc {
                     if hc.state == HungerState::WellFed {
                         offensive_bonus += 1;
+                        off_hand_bonus = off_hand_bonus.map(|bonus| bonus + 1);
                     }
                 }
 
@@ -83,6 +103,13 @@ impl<'a> System<'a> for MeleeCombatSystem {
 
                             let pos = positions.get(wants_melee.target);
 
+                            // Only report fights that happen within the player's
+                            // view, monsters squabbling elsewhere go unnoticed
+                            let witnessed = match pos {
+                                Some(pos) => map.visible_tiles[map.xy_idx(pos.x, pos.y)],
+                                None => false,
+                            };
+
                             // ADd a particle effect to the targets position
                             if let Some(pos) = pos {
                                 particle_builder.request(
@@ -95,27 +122,74 @@ impl<'a> System<'a> for MeleeCombatSystem {
                                 );
                             }
 
-                            let damage = i32::max(
-                                0,
-                                (stats.power + offensive_bonus)
-                                    - (target_stats.defense + defensive_bonus),
-                            );
+                            // Fighting is noisy
+                            if let Some(pos) = pos {
+                                noise.request(pos.x, pos.y, 8);
+                            }
 
-                            if damage == 0 {
-                                log.entries.push(format!(
-                                    "{} is unable to hurt {}",
-                                    &name.name, &target_name.name
-                                ));
-                            } else {
-                                log.entries.push(format!(
-                                    "{} hits {}, for {} hp.",
-                                    &name.name, &target_name.name, damage
-                                ));
-                                SufferDamage::new_damage(
-                                    &mut inflict_damage,
-                                    wants_melee.target,
-                                    damage,
+                            // A weapon in the off hand follows up with a
+                            // weaker strike, if the first didn't finish the job
+                            let mut strikes = vec![(offensive_bonus, false)];
+                            if let Some(bonus) = off_hand_bonus {
+                                strikes.push((bonus, true));
+                            }
+                            let mut target_hp = target_stats.hp;
+                            for (bonus, off_hand) in strikes {
+                                if target_hp <= 0 {
+                                    break;
+                                }
+                                let mut damage = i32::max(
+                                    0,
+                                    (stats.power + bonus)
+                                        - (target_stats.defense + defensive_bonus),
                                 );
+
+                                // Catching a target that hasn't noticed you yet
+                                // hits twice as hard, and certainly gets its attention
+                                let mut sneak_attack = false;
+                                if let Some(aware) = awareness.get_mut(wants_melee.target) {
+                                    if aware.state != AwarenessState::Aware {
+                                        sneak_attack = true;
+                                        damage *= 2;
+                                        aware.state = AwarenessState::Aware;
+                                    }
+                                }
+
+                                if damage == 0 {
+                                    if witnessed {
+                                        log.entries.push(format!(
+                                            "{} is unable to hurt {}",
+                                            &name.name, &target_name.name
+                                        ));
+                                    }
+                                } else {
+                                    if witnessed && sneak_attack {
+                                        log.entries.push(format!(
+                                            "{} sneak attacks {}, for {} hp!",
+                                            &name.name, &target_name.name, damage
+                                        ));
+                                    } else if witnessed && off_hand {
+                                        log.entries.push(format!(
+                                            "{} strikes {} with the off hand, for {} hp.",
+                                            &name.name, &target_name.name, damage
+                                        ));
+                                    } else if witnessed {
+                                        log.entries.push(format!(
+                                            "{} hits {}, for {} hp.",
+                                            &name.name, &target_name.name, damage
+                                        ));
+                                    }
+                                    target_hp -= damage;
+                                    SufferDamage::new_damage(
+                                        &mut inflict_damage,
+                                        wants_melee.target,
+                                        damage,
+                                        DamageSource::new(
+                                            format!("a {}", name.name),
+                                            entity == *player_entity,
+                                        ),
+                                    );
+                                }
                             }
                         }
                     }
@@ -1,8 +1,9 @@
 use super::{
-    Confusion, EntityMoved, Map, MonsterAI, ParticleBuilder, Position, RunState, Viewshed,
-    WantsToMelee,
+    gamelog::GameLog, Awareness, AwarenessState, CombatStats, Confusion, EntityMoved, Faction,
+    FactionTable, Map, MonsterAI, Name, ParticleBuilder, Position, Reaction, RunState, Stealth,
+    Viewshed, WantsToMelee, WantsToPickupItem, WantsToUseItem,
 };
-use rltk::Point;
+use rltk::{Point, RandomNumberGenerator};
 use specs::prelude::*;
 
 pub struct MonsterAISystem {}
@@ -12,9 +13,8 @@ impl<'a> System<'a> for MonsterAISystem {
     type SystemData = (
         WriteExpect<'a, Map>,
         WriteStorage<'a, EntityMoved>,
-        ReadExpect<'a, Point>,
-        ReadExpect<'a, Entity>,
         ReadExpect<'a, RunState>,
+        ReadExpect<'a, FactionTable>,
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
         WriteStorage<'a, MonsterAI>,
@@ -22,15 +22,24 @@ impl<'a> System<'a> for MonsterAISystem {
         WriteStorage<'a, WantsToMelee>,
         WriteStorage<'a, Confusion>,
         WriteExpect<'a, ParticleBuilder>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, WantsToUseItem>,
+        ReadStorage<'a, WantsToPickupItem>,
+        WriteStorage<'a, Awareness>,
+        ReadStorage<'a, Stealth>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             mut map,
             mut entity_moved,
-            player_pt,
-            player_entity,
             runstate,
+            faction_table,
             entities,
             mut viewsheds,
             mut ais,
@@ -38,14 +47,34 @@ impl<'a> System<'a> for MonsterAISystem {
             mut wants_to_melee,
             mut confusion,
             mut particle_builder,
+            factions,
+            combat_stats,
+            wants_to_use,
+            wants_to_pickup,
+            mut awareness,
+            stealths,
+            names,
+            player_entity,
+            mut rng,
+            mut log,
         ) = data;
 
         if *runstate != RunState::MonsterTurn {
             return;
         }
 
-        for (entity, mut viewshed, mut ai, mut pos) in
-            (&entities, &mut viewsheds, &mut ais, &mut positions).join()
+        // Entities that chose to use or pick up an item have already spent
+        // their turn
+        for (entity, viewshed, ai, pos, faction, _using, _picking_up) in (
+            &entities,
+            &mut viewsheds,
+            &mut ais,
+            &mut positions,
+            &factions,
+            !&wants_to_use,
+            !&wants_to_pickup,
+        )
+            .join()
         {
             let mut can_act = true;
 
@@ -70,42 +99,167 @@ impl<'a> System<'a> for MonsterAISystem {
                 );
             }
 
-            if can_act {
-                if viewshed.visible_tiles.contains(&*player_pt) {
-                    ai.target_point = Some(*player_pt);
+            if !can_act {
+                continue;
+            }
+
+            let my_pt = Point::new(pos.x, pos.y);
+
+            let (mut nearest_hostile, mut flee_from) = observe_surroundings(
+                entity,
+                faction,
+                viewshed,
+                my_pt,
+                &map,
+                &faction_table,
+                &factions,
+                &combat_stats,
+            );
+
+            // Monsters only react to enemies they have noticed. Those without
+            // `Awareness` always notice everything
+            if let Some(aware) = awareness.get_mut(entity) {
+                match aware.state {
+                    AwarenessState::Asleep => continue,
+                    AwarenessState::Unaware => {
+                        let noticed = match nearest_hostile {
+                            Some((target, _, distance)) => {
+                                let stealth = match stealths.get(target) {
+                                    Some(s) if s.sneaking => s.skill + 5,
+                                    Some(s) => s.skill,
+                                    None => 0,
+                                };
+                                rng.roll_dice(1, 20) + aware.perception
+                                    >= 10 + stealth + distance as i32
+                            }
+                            None => false,
+                        };
+
+                        if noticed {
+                            aware.state = AwarenessState::Aware;
+                            if let Some((target, _, _)) = nearest_hostile {
+                                if target == *player_entity {
+                                    log.entries.push(format!(
+                                        "The {} notices you!",
+                                        names.get(entity).unwrap().name
+                                    ));
+                                }
+                            }
+                        } else {
+                            nearest_hostile = None;
+                            flee_from.clear();
+                        }
+                    }
+                    AwarenessState::Aware => {}
                 }
+            }
 
-                if let Some(pt) = ai.target_point {
-                    let distance =
-                        rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), pt);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let mut destination: Option<usize> = None;
+
+            if !flee_from.is_empty() {
+                // Run downhill away from everything we're afraid of
+                let flee_map =
+                    rltk::DijkstraMap::new(map.width, map.height, &flee_from, &*map, 100.0);
+                destination = rltk::DijkstraMap::find_highest_exit(&flee_map, my_idx, &*map);
+                ai.target_point = None;
+            } else {
+                if let Some((target, target_pt, distance)) = nearest_hostile {
+                    ai.target_point = Some(target_pt);
 
                     if distance < 1.5 {
                         wants_to_melee
-                            .insert(
-                                entity,
-                                WantsToMelee {
-                                    target: *player_entity,
-                                },
-                            )
+                            .insert(entity, WantsToMelee { target })
                             .expect("Unable to insert attack.");
-                    } else {
-                        let path = rltk::a_star_search(
-                            map.xy_idx(pos.x, pos.y) as i32,
-                            map.xy_idx(pt.x, pt.y) as i32,
-                            &mut *map,
-                        );
-                        if path.success && path.steps.len() > 1 {
-                            pos.x = path.steps[1] as i32 % map.width;
-                            pos.y = path.steps[1] as i32 / map.width;
-                            viewshed.dirty = true;
-                            // Add a tag that monster moved
-                            entity_moved
-                                .insert(entity, EntityMoved {})
-                                .expect("Unable to insert EntityMoved on monster entity");
-                        }
+                        continue;
+                    }
+                }
+
+                if let Some(pt) = ai.target_point {
+                    // Reached the last known position of the target and there
+                    // is nothing here, so give up the chase
+                    if pt == my_pt {
+                        ai.target_point = None;
+                        continue;
+                    }
+
+                    // The target tile may be blocked by another monster, which
+                    // would stop A* from ever finding it
+                    let target_idx = map.xy_idx(pt.x, pt.y);
+                    let target_blocked = map.blocked[target_idx];
+                    map.blocked[target_idx] = false;
+                    let path = rltk::a_star_search(my_idx as i32, target_idx as i32, &*map);
+                    map.blocked[target_idx] = target_blocked;
+
+                    if path.success && path.steps.len() > 1 && !map.blocked[path.steps[1]] {
+                        destination = Some(path.steps[1]);
                     }
                 }
             }
+
+            // The player doesn't block tiles, so whether fleeing, chasing or
+            // following a noise, stop short rather than walk onto them
+            let destination =
+                destination.filter(|idx| !map.tile_content[*idx].contains(&*player_entity));
+            if let Some(destination) = destination {
+                map.blocked[my_idx] = false;
+                map.blocked[destination] = true;
+                pos.x = destination as i32 % map.width;
+                pos.y = destination as i32 / map.width;
+                viewshed.dirty = true;
+                // Add a tag that monster moved
+                entity_moved
+                    .insert(entity, EntityMoved {})
+                    .expect("Unable to insert EntityMoved on monster entity");
+            }
         }
     }
 }
+
+/// Looks at everything in `viewshed` and decides how `entity` reacts to it.
+/// Returns the nearest thing it wants to attack (with its position and
+/// distance), and the map indices of everything it wants to run away from
+#[allow(clippy::too_many_arguments)]
+pub fn observe_surroundings(
+    entity: Entity,
+    faction: &Faction,
+    viewshed: &Viewshed,
+    origin: Point,
+    map: &Map,
+    faction_table: &FactionTable,
+    factions: &ReadStorage<Faction>,
+    combat_stats: &ReadStorage<CombatStats>,
+) -> (Option<(Entity, Point, f32)>, Vec<usize>) {
+    let mut nearest_hostile: Option<(Entity, Point, f32)> = None;
+    let mut flee_from: Vec<usize> = Vec::new();
+
+    for tile in viewshed.visible_tiles.iter() {
+        let idx = map.xy_idx(tile.x, tile.y);
+        for other in map.tile_content[idx].iter() {
+            if *other == entity {
+                continue;
+            }
+            let other_faction = match factions.get(*other) {
+                Some(f) => f,
+                None => continue,
+            };
+            match faction_table.reaction(&faction.name, &other_faction.name) {
+                Reaction::Ignore => {}
+                Reaction::Flee => flee_from.push(idx),
+                Reaction::Attack => {
+                    // Only bother attacking things that can be hurt
+                    if combat_stats.get(*other).is_none() {
+                        continue;
+                    }
+                    let distance = rltk::DistanceAlg::Pythagoras.distance2d(origin, *tile);
+                    match nearest_hostile {
+                        Some((_, _, d)) if d <= distance => {}
+                        _ => nearest_hostile = Some((*other, *tile, distance)),
+                    }
+                }
+            }
+        }
+    }
+
+    (nearest_hostile, flee_from)
+}
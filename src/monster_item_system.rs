@@ -0,0 +1,237 @@
+use super::{
+    observe_surroundings, AreaOfEffect, Awareness, AwarenessState, CombatStats, Confusion, Cursed,
+    DefenseBonus, EquipmentSlot, Equippable, Equipped, Faction, FactionTable, InBackpack,
+    InflictsDamage, Item, ItemCollector, Map, MeleePowerBonus, MonsterAI, Position,
+    ProvidesHealing, Ranged, RunState, SpellCaster, TwoHanded, Viewshed, WantsToPickupItem,
+    WantsToUseItem,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Decides when monsters pick up, equip and use items. Runs before
+/// `MonsterAISystem`, which skips any monster that has used its turn here
+pub struct MonsterItemSystem {}
+
+impl<'a> System<'a> for MonsterItemSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, RunState>,
+        ReadExpect<'a, FactionTable>,
+        Entities<'a>,
+        ReadStorage<'a, MonsterAI>,
+        ReadStorage<'a, ItemCollector>,
+        ReadStorage<'a, SpellCaster>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Equippable>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleePowerBonus>,
+        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, Ranged>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, WantsToPickupItem>,
+        WriteStorage<'a, WantsToUseItem>,
+        (ReadStorage<'a, Awareness>, ReadStorage<'a, Confusion>),
+        ReadStorage<'a, Cursed>,
+        ReadStorage<'a, TwoHanded>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            map,
+            runstate,
+            faction_table,
+            entities,
+            monsters,
+            collectors,
+            casters,
+            viewsheds,
+            positions,
+            factions,
+            combat_stats,
+            items,
+            backpack,
+            equippables,
+            equipped,
+            melee_bonuses,
+            defense_bonuses,
+            healing,
+            ranged,
+            inflicts_damage,
+            aoe,
+            mut wants_pickup,
+            mut wants_use,
+            (awareness, confusion),
+            cursed,
+            two_handed,
+        ) = data;
+
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        // How much an equippable item is worth having
+        let item_score = |item: Entity| -> i32 {
+            melee_bonuses.get(item).map_or(0, |b| b.power)
+                + defense_bonuses.get(item).map_or(0, |b| b.defense)
+        };
+
+        for (entity, _monster, _collector, stats, pos) in
+            (&entities, &monsters, &collectors, &combat_stats, &positions).join()
+        {
+            // A confused monster loses its turn in `MonsterAISystem`, so it
+            // mustn't spend it here first
+            if confusion.get(entity).is_some() {
+                continue;
+            }
+
+            // Sleeping monsters don't rummage through their packs, and only
+            // those that have noticed their enemies will cast at them
+            let aware = match awareness.get(entity) {
+                Some(a) if a.state == AwarenessState::Asleep => continue,
+                Some(a) => a.state == AwarenessState::Aware,
+                None => true,
+            };
+
+            let carried: Vec<Entity> = (&entities, &backpack)
+                .join()
+                .filter(|(_, pack)| pack.owner == entity)
+                .map(|(item, _)| item)
+                .collect();
+
+            // Drink a healing potion when badly hurt
+            if stats.hp < stats.max_hp / 2 {
+                if let Some(potion) = carried.iter().find(|i| healing.get(**i).is_some()) {
+                    wants_use
+                        .insert(
+                            entity,
+                            WantsToUseItem {
+                                item: *potion,
+                                target: None,
+                            },
+                        )
+                        .expect("Unable to insert intent");
+                    continue;
+                }
+            }
+
+            // Read an offensive scroll at the nearest visible enemy in range
+            if aware && casters.get(entity).is_some() {
+                if let (Some(viewshed), Some(faction)) =
+                    (viewsheds.get(entity), factions.get(entity))
+                {
+                    let my_pt = Point::new(pos.x, pos.y);
+                    let (nearest_hostile, _) = observe_surroundings(
+                        entity,
+                        faction,
+                        viewshed,
+                        my_pt,
+                        &map,
+                        &faction_table,
+                        &factions,
+                        &combat_stats,
+                    );
+
+                    if let Some((_, target_pt, distance)) = nearest_hostile {
+                        let scroll = carried.iter().find(|i| {
+                            let in_range = match ranged.get(**i) {
+                                Some(r) => distance <= r.range as f32,
+                                None => false,
+                            };
+                            // Don't catch yourself in the blast
+                            let safe = match aoe.get(**i) {
+                                Some(a) => distance > a.radius as f32,
+                                None => true,
+                            };
+                            in_range && safe && inflicts_damage.get(**i).is_some()
+                        });
+
+                        if let Some(scroll) = scroll {
+                            wants_use
+                                .insert(
+                                    entity,
+                                    WantsToUseItem {
+                                        item: *scroll,
+                                        target: Some(target_pt),
+                                    },
+                                )
+                                .expect("Unable to insert intent");
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            // Equip anything better than everything it would take the place
+            // of, counting both hands for weapons that need them, unless
+            // any of that is cursed and won't come off
+            let upgrade = carried.iter().find(|item| {
+                if let Some(can_equip) = equippables.get(**item) {
+                    let current = (&entities, &equipped)
+                        .join()
+                        .filter(|(e, worn)| {
+                            worn.owner == entity
+                                && (worn.slot == can_equip.slot
+                                    || (two_handed.get(**item).is_some()
+                                        && worn.slot == EquipmentSlot::Shield)
+                                    || (can_equip.slot == EquipmentSlot::Shield
+                                        && two_handed.get(*e).is_some()))
+                        })
+                        .map(|(e, _)| {
+                            if cursed.get(e).is_some() {
+                                i32::MAX
+                            } else {
+                                item_score(e)
+                            }
+                        })
+                        .fold(None, |total: Option<i32>, score| {
+                            Some(total.unwrap_or(0).saturating_add(score))
+                        });
+                    match current {
+                        Some(score) => item_score(**item) > score,
+                        None => true,
+                    }
+                } else {
+                    false
+                }
+            });
+
+            if let Some(upgrade) = upgrade {
+                wants_use
+                    .insert(
+                        entity,
+                        WantsToUseItem {
+                            item: *upgrade,
+                            target: None,
+                        },
+                    )
+                    .expect("Unable to insert intent");
+                continue;
+            }
+
+            // Pick up whatever is lying underfoot
+            let idx = map.xy_idx(pos.x, pos.y);
+            let underfoot = map.tile_content[idx]
+                .iter()
+                .find(|e| items.get(**e).is_some() && positions.get(**e).is_some());
+            if let Some(item) = underfoot {
+                wants_pickup
+                    .insert(
+                        entity,
+                        WantsToPickupItem {
+                            collected_by: entity,
+                            item: *item,
+                        },
+                    )
+                    .expect("Unable to insert want to pickup");
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::{statistics, Equipped, GameLog, InBackpack, Map, Name, Point, RunStats, TileType};
+use specs::prelude::*;
+
+/// How many of the final log messages go in the morgue file
+const MORGUE_LOG_LINES: usize = 20;
+
+/// The summary of a finished game, shown on the game over screen
+pub struct Morgue {
+    pub summary: Vec<String>,
+    /// Where the full morgue was written, if it could be
+    pub file: Option<String>,
+    /// Where the run's statistics were exported, if they could be
+    pub stats_file: Option<String>,
+}
+
+/// Writes up the player's death and inserts it as the `Morgue` resource,
+/// saving it to a file where possible
+pub fn create_morgue(ecs: &mut World) {
+    let summary = morgue_summary(ecs);
+    let mut text = summary.clone();
+    text.push(String::new());
+    text.extend(morgue_details(ecs));
+
+    let file = write_morgue(&text);
+    let stats_file = statistics::export_stats(&ecs.fetch::<RunStats>());
+    ecs.insert(Morgue {
+        summary,
+        file,
+        stats_file,
+    });
+}
+
+/// The headline facts about the run
+fn morgue_summary(ecs: &World) -> Vec<String> {
+    let stats = ecs.fetch::<RunStats>();
+    let map = ecs.fetch::<Map>();
+
+    let cause = match &stats.cause_of_death {
+        Some(cause) => cause.clone(),
+        None => "something unknown".to_string(),
+    };
+    let total_kills: i32 = stats.kills.values().sum();
+
+    let mut lines = vec![
+        format!("Killed by {} on depth {}.", cause, map.depth),
+        format!(
+            "Survived {} turns and slew {} monsters.",
+            stats.turns, total_kills
+        ),
+        format!("Seed: {}", stats.seed),
+    ];
+
+    if !stats.kills.is_empty() {
+        lines.push(String::new());
+        lines.push("Kills:".to_string());
+        for (name, count) in stats.kills.iter() {
+            lines.push(format!("  {} x{}", name, count));
+        }
+    }
+
+    if !stats.items_used.is_empty() {
+        lines.push(String::new());
+        lines.push("Items used:".to_string());
+        for (name, count) in stats.items_used.iter() {
+            lines.push(format!("  {} x{}", name, count));
+        }
+    }
+
+    lines
+}
+
+/// Equipment, inventory, final messages and the map, for the morgue file
+fn morgue_details(ecs: &World) -> Vec<String> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let log = ecs.fetch::<GameLog>();
+    let map = ecs.fetch::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+
+    let mut lines = vec!["Equipment:".to_string()];
+    for (eq, name) in (&equipped, &names).join() {
+        if eq.owner == *player_entity {
+            lines.push(format!("  {}: {}", eq.slot.label(), name.name));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Inventory:".to_string());
+    for (pack, name) in (&backpack, &names).join() {
+        if pack.owner == *player_entity {
+            lines.push(format!("  {}", name.name));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Last messages:".to_string());
+    let first = log.entries.len().saturating_sub(MORGUE_LOG_LINES);
+    for entry in log.entries[first..].iter() {
+        lines.push(format!("  {}", entry));
+    }
+
+    lines.push(String::new());
+    lines.push("Map:".to_string());
+    for y in 0..map.height {
+        let mut row = String::new();
+        for x in 0..map.width {
+            let idx = map.xy_idx(x, y);
+            let c = if x == player_pos.x && y == player_pos.y {
+                '@'
+            } else if !map.revealed_tiles[idx] {
+                ' '
+            } else {
+                match map.tiles[idx] {
+                    TileType::Wall => '#',
+                    TileType::Floor => '.',
+                    TileType::DownStairs => '>',
+                }
+            };
+            row.push(c);
+        }
+        lines.push(row.trim_end().to_string());
+    }
+
+    lines
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_morgue(text: &[String]) -> Option<String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = format!("./morgue-{}.txt", timestamp);
+    match std::fs::write(&path, text.join("\n")) {
+        Ok(_) => Some(path),
+        Err(_) => None,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_morgue(_text: &[String]) -> Option<String> {
+    None
+}
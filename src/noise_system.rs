@@ -0,0 +1,192 @@
+use super::{gamelog::GameLog, Awareness, AwarenessState, Map, MonsterAI, Name, Position};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+struct NoiseRequest {
+    x: i32,
+    y: i32,
+    volume: i32,
+}
+
+/// Collects noises made during a turn so `NoiseSystem` can let monsters hear
+/// them
+pub struct NoiseBuilder {
+    requests: Vec<NoiseRequest>,
+}
+
+impl NoiseBuilder {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> NoiseBuilder {
+        NoiseBuilder {
+            requests: Vec::new(),
+        }
+    }
+
+    /// Makes a noise at `x, y` that can be heard up to `volume` steps away
+    pub fn request(&mut self, x: i32, y: i32, volume: i32) {
+        if volume > 0 {
+            self.requests.push(NoiseRequest { x, y, volume });
+        }
+    }
+}
+
+/// Propagates noises along walkable paths, waking sleeping monsters and
+/// drawing unaware ones towards the source
+pub struct NoiseSystem {}
+
+impl<'a> System<'a> for NoiseSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        WriteExpect<'a, NoiseBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Awareness>,
+        WriteStorage<'a, MonsterAI>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            mut noise_builder,
+            mut rng,
+            mut log,
+            entities,
+            positions,
+            names,
+            mut awareness,
+            mut ais,
+        ) = data;
+
+        if noise_builder.requests.is_empty() {
+            return;
+        }
+
+        // Sound travels around monsters, only walls get in its way. Swap in
+        // a blocked list of just the walls while we path, then put it back
+        let blocked = map.blocked.clone();
+        map.populate_blocked();
+
+        for noise in noise_builder.requests.iter() {
+            let source = map.xy_idx(noise.x, noise.y);
+            let sound_map = rltk::DijkstraMap::new(
+                map.width,
+                map.height,
+                &[source],
+                &*map,
+                noise.volume as f32,
+            );
+
+            for (entity, pos, aware, ai) in (&entities, &positions, &mut awareness, &mut ais).join()
+            {
+                let distance = sound_map.map[map.xy_idx(pos.x, pos.y)];
+                if distance > noise.volume as f32 {
+                    continue;
+                }
+                let loudness = noise.volume - distance as i32;
+
+                match aware.state {
+                    AwarenessState::Asleep => {
+                        // The louder and closer, the more likely to wake up
+                        if rng.roll_dice(1, 20) + aware.perception + loudness >= 15 {
+                            aware.state = AwarenessState::Unaware;
+                            ai.target_point = Some(Point::new(noise.x, noise.y));
+                            if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                                if let Some(name) = names.get(entity) {
+                                    log.entries.push(format!("The {} wakes up.", name.name));
+                                }
+                            }
+                        }
+                    }
+                    AwarenessState::Unaware => {
+                        ai.target_point = Some(Point::new(noise.x, noise.y));
+                    }
+                    AwarenessState::Aware => {
+                        if ai.target_point.is_none() {
+                            ai.target_point = Some(Point::new(noise.x, noise.y));
+                        }
+                    }
+                }
+            }
+        }
+
+        map.blocked = blocked;
+        noise_builder.requests.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::test_world, TileType};
+
+    /// Puts an unaware monster at `x, y`
+    fn listener(ecs: &mut World, x: i32, y: i32) -> Entity {
+        ecs.create_entity()
+            .with(Position { x, y })
+            .with(MonsterAI::new())
+            .with(Awareness {
+                state: AwarenessState::Unaware,
+                perception: 0,
+            })
+            .build()
+    }
+
+    fn make_noise(ecs: &mut World, x: i32, y: i32, volume: i32) {
+        ecs.insert(NoiseBuilder::new());
+        ecs.write_resource::<NoiseBuilder>().request(x, y, volume);
+        NoiseSystem {}.run_now(ecs);
+    }
+
+    fn target(ecs: &World, entity: Entity) -> Option<Point> {
+        ecs.read_storage::<MonsterAI>()
+            .get(entity)
+            .unwrap()
+            .target_point
+    }
+
+    #[test]
+    fn unaware_monsters_head_for_a_noise_they_hear() {
+        let mut ecs = test_world();
+        let monster = listener(&mut ecs, 4, 1);
+        make_noise(&mut ecs, 1, 1, 4);
+        assert_eq!(target(&ecs, monster), Some(Point::new(1, 1)));
+    }
+
+    #[test]
+    fn noise_fades_beyond_its_volume() {
+        let mut ecs = test_world();
+        let monster = listener(&mut ecs, 8, 1);
+        make_noise(&mut ecs, 1, 1, 4);
+        assert_eq!(target(&ecs, monster), None);
+    }
+
+    #[test]
+    fn noise_goes_around_walls_not_through_them() {
+        let mut ecs = test_world();
+        // Wall off the top row, leaving a gap at the far end
+        {
+            let mut map = ecs.write_resource::<Map>();
+            for x in 1..8 {
+                let idx = map.xy_idx(x, 2);
+                map.tiles[idx] = TileType::Wall;
+            }
+        }
+        let monster = listener(&mut ecs, 1, 3);
+        // Two steps away as the crow flies, but over a dozen around the wall
+        make_noise(&mut ecs, 1, 1, 6);
+        assert_eq!(target(&ecs, monster), None);
+        make_noise(&mut ecs, 1, 1, 16);
+        assert_eq!(target(&ecs, monster), Some(Point::new(1, 1)));
+    }
+
+    #[test]
+    fn quiet_requests_are_ignored() {
+        let mut builder = NoiseBuilder::new();
+        builder.request(1, 1, 0);
+        assert!(builder.requests.is_empty());
+    }
+}
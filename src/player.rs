@@ -1,12 +1,22 @@
 use super::{
-    CombatStats, EntityMoved, GameLog, HungerClock, HungerState, Item, Map, MonsterAI, Player,
-    Position, RunState, State, TileType, Viewshed, WantsToMelee, WantsToPickupItem,
+    export_stats, AutoPickup, CombatStats, Command, Consumable, EntityMoved, GameLog, Gold,
+    HungerClock, HungerState, Item, Map, MonsterAI, NoiseBuilder, Player, Position, RenderMode,
+    RunState, RunStats, Settings, ShopMode, State, Stealth, TileType, Vendor, Viewshed,
+    WantsToMelee, WantsToPickupItem,
 };
 use rltk::{Point, Rltk, VirtualKeyCode};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
-pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+/// Moves the player, attacking whatever is in the way. Bumping into a
+/// shopkeeper opens their shop instead
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    move_player(delta_x, delta_y, ecs, false)
+}
+
+/// Moves the player as `try_move_player` does, but with `force_attack` set
+/// shopkeepers get attacked like anyone else rather than traded with
+fn move_player(delta_x: i32, delta_y: i32, ecs: &mut World, force_attack: bool) -> RunState {
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -15,6 +25,15 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
     let map = ecs.fetch::<Map>();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
+    let stealths = ecs.read_storage::<Stealth>();
+    let mut hunger_clocks = ecs.write_storage::<HungerClock>();
+    let mut noise = ecs.write_resource::<NoiseBuilder>();
+    let settings = ecs.fetch::<Settings>();
+    let items = ecs.read_storage::<Item>();
+    let consumables = ecs.read_storage::<Consumable>();
+    let mut wants_pickup = ecs.write_storage::<WantsToPickupItem>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let gold = ecs.read_storage::<Gold>();
 
     for (entity, _player, pos, viewshed) in
         (&entities, &players, &mut positions, &mut viewsheds).join()
@@ -24,11 +43,17 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             || pos.y + delta_y < 1
             || pos.y + delta_y > map.height - 1
         {
-            return;
+            return RunState::PlayerTurn;
         }
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
         for potential_target in map.tile_content[destination_idx].iter() {
+            if !force_attack && vendors.get(*potential_target).is_some() {
+                return RunState::ShowShop {
+                    vendor: *potential_target,
+                    mode: ShopMode::Buy,
+                };
+            }
             let target = combat_stats.get(*potential_target);
             if let Some(_target) = target {
                 wants_to_melee
@@ -39,13 +64,13 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                         },
                     )
                     .expect("Add target failed");
-                return;
+                return RunState::PlayerTurn;
             }
         }
 
         if !map.blocked[destination_idx] {
-            pos.x = min(79, max(0, pos.x + delta_x));
-            pos.y = min(49, max(0, pos.y + delta_y));
+            pos.x = min(map.width - 1, max(0, pos.x + delta_x));
+            pos.y = min(map.height - 1, max(0, pos.y + delta_y));
 
             viewshed.dirty = true;
             let mut ppos = ecs.write_resource::<Point>();
@@ -56,71 +81,146 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             entity_moved
                 .insert(entity, EntityMoved {})
                 .expect("Unable to insert EntityMoved on player");
+
+            // Footsteps can be heard nearby, unless sneaking, which is quiet
+            // but tiring
+            match stealths.get(entity) {
+                Some(stealth) if stealth.sneaking => {
+                    noise.request(pos.x, pos.y, 1);
+                    if let Some(hc) = hunger_clocks.get_mut(entity) {
+                        hc.duration -= 1;
+                    }
+                }
+                _ => noise.request(pos.x, pos.y, 4),
+            }
+
+            // Pick up the first item here the auto-pickup rules allow
+            let auto_item =
+                map.tile_content[destination_idx]
+                    .iter()
+                    .find(|e| match settings.auto_pickup {
+                        AutoPickup::Off => false,
+                        AutoPickup::Consumables => {
+                            items.get(**e).is_some()
+                                && (consumables.get(**e).is_some() || gold.get(**e).is_some())
+                        }
+                        AutoPickup::Everything => items.get(**e).is_some(),
+                    });
+            if let Some(item) = auto_item {
+                wants_pickup
+                    .insert(
+                        entity,
+                        WantsToPickupItem {
+                            collected_by: entity,
+                            item: *item,
+                        },
+                    )
+                    .expect("Unable to insert want to pickup");
+            }
         }
     }
+    RunState::PlayerTurn
 }
 
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    // Player movement
-    match ctx.key {
+    let key = match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
-        Some(key) => match key {
-            VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-                try_move_player(-1, 0, &mut gs.ecs)
-            }
-
-            VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-                try_move_player(1, 0, &mut gs.ecs)
-            }
+        Some(key) => key,
+    };
+
+    // Save and Quit. Escape can't be rebound, so the menu is always reachable
+    if key == VirtualKeyCode::Escape {
+        return RunState::MainMenu {
+            menu_selection: crate::MainMenuSelection::Quit,
+        };
+    }
 
-            VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-                try_move_player(0, -1, &mut gs.ecs)
-            }
+    // The debug tools have fixed keys too, when they're built in
+    #[cfg(feature = "debug-console")]
+    {
+        if key == VirtualKeyCode::Grave {
+            return RunState::ShowDebugConsole;
+        }
+        if key == VirtualKeyCode::F12 {
+            return RunState::ShowInspector {
+                entity: None,
+                show_viewshed: false,
+                show_path: false,
+                scroll: 0,
+            };
+        }
+    }
 
-            VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-                try_move_player(0, 1, &mut gs.ecs)
-            }
+    let command = gs.ecs.fetch::<Settings>().keymap.command(key);
+    match command {
+        None => return RunState::AwaitingInput,
+        Some(command) => match command {
+            // Player movement. Holding shift attacks even a shopkeeper
+            Command::MoveWest => return move_player(-1, 0, &mut gs.ecs, ctx.shift),
+            Command::MoveEast => return move_player(1, 0, &mut gs.ecs, ctx.shift),
+            Command::MoveNorth => return move_player(0, -1, &mut gs.ecs, ctx.shift),
+            Command::MoveSouth => return move_player(0, 1, &mut gs.ecs, ctx.shift),
 
             // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::U => try_move_player(1, -1, &mut gs.ecs),
-
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => try_move_player(-1, -1, &mut gs.ecs),
-
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::M => try_move_player(1, 1, &mut gs.ecs),
-
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::N => try_move_player(-1, 1, &mut gs.ecs),
+            Command::MoveNorthEast => return move_player(1, -1, &mut gs.ecs, ctx.shift),
+            Command::MoveNorthWest => return move_player(-1, -1, &mut gs.ecs, ctx.shift),
+            Command::MoveSouthEast => return move_player(1, 1, &mut gs.ecs, ctx.shift),
+            Command::MoveSouthWest => return move_player(-1, 1, &mut gs.ecs, ctx.shift),
 
             // Pickup Item
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
+            Command::PickUp => get_item(&mut gs.ecs),
 
             // Open Inventory Screen
-            VirtualKeyCode::I => return RunState::ShowInventory,
+            Command::Inventory => return RunState::ShowInventory,
 
             // Open Drop-Item Screen
-            VirtualKeyCode::D => return RunState::ShowDropItem,
-
-            // Save and Quit
-            VirtualKeyCode::Escape => {
-                return RunState::MainMenu {
-                    menu_selection: crate::MainMenuSelection::Quit,
-                }
-            }
+            Command::Drop => return RunState::ShowDropItem,
 
             // Use Stairs down
-            VirtualKeyCode::Period => {
+            Command::Descend => {
                 if try_next_level(&mut gs.ecs) {
                     return RunState::NextLevel;
                 }
             }
 
             // Skip Turn
-            VirtualKeyCode::Numpad5 => return skip_turn(&mut gs.ecs),
-            VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
+            Command::Wait => return skip_turn(&mut gs.ecs),
 
             // Show Unequip menu
-            VirtualKeyCode::R => return RunState::ShowRemoveItem,
+            Command::Unequip => return RunState::ShowRemoveItem,
+            Command::Character => return RunState::ShowCharacter,
+            Command::Craft => return RunState::ShowCrafting,
+
+            // Toggle sneaking
+            Command::Sneak => {
+                toggle_sneak(&mut gs.ecs);
+                return RunState::AwaitingInput;
+            }
+
+            // Look around the map with a cursor, starting on the player
+            Command::Look => {
+                let cursor = *gs.ecs.fetch::<Point>();
+                return RunState::ShowLook { cursor };
+            }
 
-            _ => return RunState::AwaitingInput,
+            // Write out the statistics so far for analysis
+            Command::ExportStats => {
+                export_run_stats(&mut gs.ecs);
+                return RunState::AwaitingInput;
+            }
+
+            // Switch between ASCII and tiles
+            Command::ToggleTiles => {
+                let mut mode = gs.ecs.write_resource::<RenderMode>();
+                *mode = match *mode {
+                    RenderMode::Ascii => RenderMode::Tiles,
+                    RenderMode::Tiles => RenderMode::Ascii,
+                };
+                return RunState::AwaitingInput;
+            }
+
+            // Show the command reference
+            Command::Help => return RunState::ShowHelp { page: 0 },
         },
     }
     RunState::PlayerTurn
@@ -160,6 +260,34 @@ fn get_item(ecs: &mut World) {
     }
 }
 
+fn toggle_sneak(ecs: &mut World) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut stealths = ecs.write_storage::<Stealth>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    if let Some(stealth) = stealths.get_mut(*player_entity) {
+        stealth.sneaking = !stealth.sneaking;
+        if stealth.sneaking {
+            gamelog.entries.push("You begin sneaking.".to_string());
+        } else {
+            gamelog.entries.push("You stop sneaking.".to_string());
+        }
+    }
+}
+
+fn export_run_stats(ecs: &mut World) {
+    let exported = export_stats(&ecs.fetch::<RunStats>());
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+    match exported {
+        Some(location) => gamelog
+            .entries
+            .push(format!("Statistics so far exported to {}.", location)),
+        None => gamelog
+            .entries
+            .push("The statistics couldn't be exported.".to_string()),
+    }
+}
+
 fn try_next_level(ecs: &mut World) -> bool {
     let player_pos = ecs.fetch::<Point>();
     let map = ecs.fetch::<Map>();
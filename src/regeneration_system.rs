@@ -0,0 +1,37 @@
+use super::{CombatStats, Equipped, Regeneration, RunState, RunStats};
+use specs::prelude::*;
+
+/// Heals anyone wearing something regenerative, a hit point at a time. Like
+/// hunger, the player mends on their turn and monsters on theirs
+pub struct RegenerationSystem {}
+
+impl<'a> System<'a> for RegenerationSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        ReadExpect<'a, RunStats>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Regeneration>,
+        WriteStorage<'a, CombatStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, runstate, run_stats, equipped, regeneration, mut combat_stats) = data;
+
+        for (worn, regen) in (&equipped, &regeneration).join() {
+            let proceed = match *runstate {
+                RunState::PlayerTurn => worn.owner == *player_entity,
+                RunState::MonsterTurn => worn.owner != *player_entity,
+                _ => false,
+            };
+            if !proceed || run_stats.turns % regen.interval.max(1) != 0 {
+                continue;
+            }
+            if let Some(stats) = combat_stats.get_mut(worn.owner) {
+                if stats.hp > 0 {
+                    stats.hp = i32::min(stats.max_hp, stats.hp + 1);
+                }
+            }
+        }
+    }
+}
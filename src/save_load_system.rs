@@ -2,28 +2,6 @@ use super::*;
 use specs::error::NoError;
 use specs::saveload::{DeserializeComponents, MarkedBuilder, SerializeComponents};
 
-// WASM-only imports
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::*;
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::JsValue;
-
-// Non-WASM-only imports
-#[cfg(not(target_arch = "wasm32"))]
-use std::fs::{read_to_string, File};
-#[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
-
-// WASM Bindings to JS for saving and loading data from storage
-#[cfg(target_arch = "wasm32")]
-#[wasm_bindgen]
-extern "C" {
-    fn save(s: &[u8]);
-
-    #[wasm_bindgen(catch)]
-    fn load() -> std::result::Result<JsValue, JsValue>;
-}
-
 /// Helper macro for serializing stores of Components to be saved
 macro_rules! serialize_individually {
     ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
@@ -95,6 +73,31 @@ pub fn register_storages(ecs: &mut World) {
     ecs.register::<SingleActivation>();
     ecs.register::<PeriodicHiding>();
     ecs.register::<RevealChance>();
+    ecs.register::<Faction>();
+    ecs.register::<ItemCollector>();
+    ecs.register::<SpellCaster>();
+    ecs.register::<Awareness>();
+    ecs.register::<Stealth>();
+    ecs.register::<LightSource>();
+    ecs.register::<GodMode>();
+    ecs.register::<IdentifiesItems>();
+    ecs.register::<Enchantment>();
+    ecs.register::<Cursed>();
+    ecs.register::<RemovesCurse>();
+    ecs.register::<Stackable>();
+    ecs.register::<Charges>();
+    ecs.register::<Weight>();
+    ecs.register::<Value>();
+    ecs.register::<Encumbrance>();
+    ecs.register::<Regeneration>();
+    ecs.register::<Sustenance>();
+    ecs.register::<Speed>();
+    ecs.register::<TwoHanded>();
+    ecs.register::<OffHand>();
+    ecs.register::<Gold>();
+    ecs.register::<Purse>();
+    ecs.register::<Vendor>();
+    ecs.register::<Sharpened>();
     // Storage registration order must match save/load order!
 }
 
@@ -149,7 +152,32 @@ where
         EntityMoved,
         SingleActivation,
         PeriodicHiding,
-        RevealChance
+        RevealChance,
+        Faction,
+        ItemCollector,
+        SpellCaster,
+        Awareness,
+        Stealth,
+        LightSource,
+        GodMode,
+        IdentifiesItems,
+        Enchantment,
+        Cursed,
+        RemovesCurse,
+        Stackable,
+        Charges,
+        Weight,
+        Value,
+        Encumbrance,
+        Regeneration,
+        Sustenance,
+        Speed,
+        TwoHanded,
+        OffHand,
+        Gold,
+        Purse,
+        Vendor,
+        Sharpened
     );
 }
 
@@ -204,17 +232,52 @@ where
         EntityMoved,
         SingleActivation,
         PeriodicHiding,
-        RevealChance
+        RevealChance,
+        Faction,
+        ItemCollector,
+        SpellCaster,
+        Awareness,
+        Stealth,
+        LightSource,
+        GodMode,
+        IdentifiesItems,
+        Enchantment,
+        Cursed,
+        RemovesCurse,
+        Stackable,
+        Charges,
+        Weight,
+        Value,
+        Encumbrance,
+        Regeneration,
+        Sustenance,
+        Speed,
+        TwoHanded,
+        OffHand,
+        Gold,
+        Purse,
+        Vendor,
+        Sharpened
     );
 }
 
-#[cfg(target_arch = "wasm32")]
+/// The storage key the game is saved under
+const SAVE_KEY: &str = "savegame";
+
+/// Saves the game. If it can't be saved the game goes on as if the save had
+/// been cancelled
 pub fn save_game(ecs: &mut World) {
     // Create helper
     let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
+    let stats = (*ecs.fetch::<super::RunStats>()).clone();
+    let identities = (*ecs.fetch::<super::ItemIdentities>()).clone();
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper { map: mapcopy })
+        .with(SerializationHelper {
+            map: mapcopy,
+            stats,
+            identities,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -225,21 +288,9 @@ pub fn save_game(ecs: &mut World) {
 
         serialize_world(ecs, &mut serializer);
 
-        let output = std::str::from_utf8(serializer.into_inner().as_slice())
-            .unwrap()
-            .to_string();
-        let window: web_sys::Window = web_sys::window().expect("no global window");
-        match window.local_storage() {
-            Ok(store) => {
-                if let Some(store) = store {
-                    let res = store.set_item("save", output.as_str());
-                    match res {
-                        _ => {}
-                    }
-                }
-            }
-
-            Err(_) => {}
+        let output = String::from_utf8(serializer.into_inner()).unwrap();
+        if let Err(e) = save_text(SAVE_KEY, &output) {
+            rltk::console::log(format!("Unable to save the game: {}", e));
         }
     }
 
@@ -247,58 +298,9 @@ pub fn save_game(ecs: &mut World) {
     ecs.delete_entity(savehelper).expect("Crash on cleanup");
 }
 
-/// Saves a new game file
-#[cfg(not(target_arch = "wasm32"))]
-pub fn save_game(ecs: &mut World) {
-    // Create helper
-    let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
-    let savehelper = ecs
-        .create_entity()
-        .with(SerializationHelper { map: mapcopy })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-
-    // Serialization
-    {
-        let writer = File::create("./savegame.json").unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
-
-        serialize_world(ecs, &mut serializer);
-    }
-
-    // Clean up
-    ecs.delete_entity(savehelper).expect("Crash on cleanup");
-}
-
 /// Checks if a save file exists
-#[cfg(not(target_arch = "wasm32"))]
 pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
-}
-
-/// Checks if a save file exists
-#[cfg(target_arch = "wasm32")]
-pub fn does_save_exist() -> bool {
-    let mut found = false;
-    let window: web_sys::Window = web_sys::window().expect("no global window");
-    match window.local_storage() {
-        Ok(store) => {
-            if let Some(store) = store {
-                match store.get_item("save") {
-                    Ok(data) => {
-                        if let Some(save) = data {
-                            found = true;
-                        }
-                    }
-                    Err(_) => {}
-                }
-            }
-        }
-
-        Err(_) => {}
-    }
-
-    return found;
+    is_saved(SAVE_KEY)
 }
 
 /// Whether the game can be quit or not
@@ -318,78 +320,12 @@ pub fn can_quit_game() -> bool {
 }
 
 /// Loads a saved game file, assuming there is one
-#[cfg(target_arch = "wasm32")]
 pub fn load_game(ecs: &mut World) {
-    let mut opt_save_data: Option<String> = None;
-    let window: web_sys::Window = web_sys::window().expect("no global window");
-    match window.local_storage() {
-        Ok(store) => {
-            if let Some(store) = store {
-                match store.get_item("save") {
-                    Ok(data) => {
-                        if let Some(save) = data {
-                            opt_save_data = Some(save);
-                        }
-                    }
-                    Err(_) => {}
-                }
-            }
-        }
-
-        Err(_) => {}
-    }
-
-    if let Some(save_data_string) = opt_save_data {
-        {
-            // Delete everything in two steps to avoid
-            // invalidation the iterator in the first pass
-            let mut to_delete = Vec::new();
-            for e in ecs.entities().join() {
-                to_delete.push(e);
-            }
-
-            for del in to_delete.iter() {
-                ecs.delete_entity(*del)
-                    .expect("load_game Entity Deletion Failed.");
-            }
-        }
-
-        let mut deserializer = serde_json::Deserializer::from_str(&save_data_string);
-
-        deserialize_world(ecs, &mut deserializer);
-
-        let mut deleteme: Option<Entity> = None;
-        {
-            let entities = ecs.entities();
-            let helper = ecs.read_storage::<SerializationHelper>();
-            let player = ecs.read_storage::<Player>();
-            let position = ecs.read_storage::<Position>();
-            for (e, h) in (&entities, &helper).join() {
-                let mut worldmap = ecs.write_resource::<super::map::Map>();
-
-                *worldmap = h.map.clone();
-                worldmap.tile_content = vec![Vec::new(); super::map::MAP_COUNT];
-                deleteme = Some(e);
-            }
-
-            for (e, _p, pos) in (&entities, &player, &position).join() {
-                let mut ppos = ecs.write_resource::<rltk::Point>();
-                *ppos = rltk::Point::new(pos.x, pos.y);
-                let mut player_resource = ecs.write_resource::<Entity>();
-                *player_resource = e;
-            }
-        }
-
-        if let Some(e) = deleteme {
-            ecs.delete_entity(e)
-                .expect("load_game Unable to delete helper");
-        }
-    }
-}
+    let data = match load_text(SAVE_KEY) {
+        Some(data) => data,
+        None => return,
+    };
 
-/// Loads a saved game file, assuming there is one
-#[cfg(not(target_arch = "wasm32"))]
-pub fn load_game(ecs: &mut World) {
     {
         // Delete everything in two steps to avoid
         // invalidation the iterator in the first pass
@@ -404,7 +340,6 @@ pub fn load_game(ecs: &mut World) {
         }
     }
 
-    let data = read_to_string("./savegame.json").unwrap();
     let mut deserializer = serde_json::Deserializer::from_str(&data);
 
     deserialize_world(ecs, &mut deserializer);
@@ -419,7 +354,9 @@ pub fn load_game(ecs: &mut World) {
             let mut worldmap = ecs.write_resource::<super::map::Map>();
 
             *worldmap = h.map.clone();
-            worldmap.tile_content = vec![Vec::new(); super::map::MAP_COUNT];
+            worldmap.rebuild_indices();
+            *ecs.write_resource::<super::RunStats>() = h.stats.clone();
+            *ecs.write_resource::<super::ItemIdentities>() = h.identities.clone();
             deleteme = Some(e);
         }
 
@@ -438,29 +375,6 @@ pub fn load_game(ecs: &mut World) {
 }
 
 /// Deletes the save file
-#[cfg(not(target_arch = "wasm32"))]
-pub fn delete_save() {
-    if Path::new("./savegame.json").exists() {
-        std::fs::remove_file("./savegame.json").expect("Unable to delete file");
-    }
-}
-/// Deletes the save file
-#[cfg(target_arch = "wasm32")]
 pub fn delete_save() {
-    if !does_save_exist() {
-        return;
-    }
-
-    let window: web_sys::Window = web_sys::window().expect("no global window");
-    match window.local_storage() {
-        Ok(store) => {
-            if let Some(store) = store {
-                match store.remove_item("save") {
-                    _ => {}
-                }
-            }
-        }
-
-        Err(_) => {}
-    }
+    delete_saved(SAVE_KEY);
 }
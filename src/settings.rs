@@ -0,0 +1,103 @@
+use super::{load_json, save_json, KeyMap};
+use rltk::Rltk;
+use serde::{Deserialize, Serialize};
+
+/// What the settings are saved under
+const SETTINGS_KEY: &str = "settings";
+
+/// Post-processing applied to the whole screen
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum PostEffect {
+    None,
+    Scanlines,
+    /// Scanlines with screen burn, for an old CRT look
+    Crt,
+}
+
+/// The font the consoles are drawn in
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum FontChoice {
+    Terminal8x8,
+    Vga8x16,
+}
+
+impl FontChoice {
+    /// Returns the font's file and the width and height of its glyphs
+    pub fn file(self) -> (&'static str, u32, u32) {
+        match self {
+            FontChoice::Terminal8x8 => ("terminal8x8.png", 8, 8),
+            FontChoice::Vga8x16 => ("vga8x16.png", 8, 16),
+        }
+    }
+}
+
+/// Which items are picked up just by walking over them
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum AutoPickup {
+    Off,
+    Consumables,
+    Everything,
+}
+
+/// Player preferences, kept between runs in a settings file. Anything missing
+/// from the file keeps its default
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub post_effect: PostEffect,
+    pub font: FontChoice,
+    pub fullscreen: bool,
+    /// How many times larger than the font's size each cell is drawn
+    pub window_scale: u32,
+    pub keymap: KeyMap,
+    /// How many messages the game log keeps
+    pub log_length: usize,
+    pub auto_pickup: AutoPickup,
+    /// Whether map generation is played back step by step, for debugging
+    /// builders
+    pub show_mapgen: bool,
+}
+
+pub const MAX_WINDOW_SCALE: u32 = 3;
+/// Log lengths the options menu cycles through
+pub const LOG_LENGTHS: [usize; 4] = [50, 100, 250, 1000];
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            post_effect: PostEffect::None,
+            font: FontChoice::Terminal8x8,
+            fullscreen: false,
+            window_scale: 1,
+            keymap: KeyMap::default(),
+            log_length: 100,
+            auto_pickup: AutoPickup::Off,
+            show_mapgen: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Applies the options that can change while the game is running. The
+    /// rest only take effect when the window is created
+    pub fn apply(&self, ctx: &mut Rltk) {
+        ctx.post_scanlines = self.post_effect != PostEffect::None;
+        ctx.post_screenburn = self.post_effect == PostEffect::Crt;
+    }
+}
+
+/// Loads the saved settings, falling back to defaults if there aren't usable
+/// ones
+pub fn load_settings() -> Settings {
+    let mut settings: Settings = load_json(SETTINGS_KEY).unwrap_or_default();
+    settings.window_scale = settings.window_scale.clamp(1, MAX_WINDOW_SCALE);
+    settings
+}
+
+/// Saves the settings for next time. If they can't be saved the game carries
+/// on with them anyway
+pub fn save_settings(settings: &Settings) {
+    if let Err(e) = save_json(SETTINGS_KEY, settings) {
+        rltk::console::log(format!("Unable to save settings: {}", e));
+    }
+}
@@ -0,0 +1,298 @@
+use super::{
+    can_carry, describe_item, gamelog::GameLog, split_stack, stack_weight, Enchantment,
+    Encumbrance, InBackpack, Map, Name, Purse, Stackable, Value, Weight,
+};
+use specs::prelude::*;
+
+/// Shopkeepers pay this fraction of what an item is worth
+const SELL_DIVISOR: i32 = 3;
+
+/// What an item is worth, with each point of enchantment adding or taking off
+/// a quarter. Items without a value can't be traded
+pub fn item_worth(ecs: &World, item: Entity) -> Option<i32> {
+    let value = ecs.read_storage::<Value>().get(item)?.value;
+    let bonus = ecs
+        .read_storage::<Enchantment>()
+        .get(item)
+        .map_or(0, |e| e.bonus);
+    Some(i32::max(1, value * (4 + bonus) / 4))
+}
+
+/// What a shopkeeper asks for one of `item`. Shops get dearer the deeper
+/// they are
+pub fn buy_price(ecs: &World, item: Entity) -> Option<i32> {
+    let depth = ecs.fetch::<Map>().depth;
+    item_worth(ecs, item).map(|worth| worth * (10 + depth) / 10)
+}
+
+/// What a shopkeeper pays for one of `item`
+pub fn sell_price(ecs: &World, item: Entity) -> Option<i32> {
+    item_worth(ecs, item).map(|worth| i32::max(1, worth / SELL_DIVISOR))
+}
+
+/// Buys one of `item` from `vendor`'s stock for the player, if they can
+/// afford it and carry it
+pub fn buy_item(ecs: &mut World, vendor: Entity, item: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let name = ecs.read_storage::<Name>().get(item).map(|n| n.name.clone());
+    let (price, name) = match (buy_price(ecs, item), name) {
+        (Some(price), Some(_)) => (price, describe_item(ecs, item)),
+        _ => return,
+    };
+    let gold = ecs
+        .read_storage::<Purse>()
+        .get(player_entity)
+        .map_or(0, |p| p.gold);
+
+    if gold < price {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You can't afford the {}.", name));
+        return;
+    }
+    let item = take_one(ecs, item);
+    if !can_carry(ecs, player_entity, item) {
+        hand_over(ecs, item, vendor);
+        ecs.fetch_mut::<GameLog>().entries.push(format!(
+            "You can't carry the {} as well, it's too heavy.",
+            name
+        ));
+        return;
+    }
+
+    if let Some(purse) = ecs.write_storage::<Purse>().get_mut(player_entity) {
+        purse.gold -= price;
+    }
+    let name = describe_item(ecs, item);
+    shift_load(ecs, player_entity, item, 1.0);
+    hand_over(ecs, item, player_entity);
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("You buy the {} for {} gold.", name, price));
+}
+
+/// Sells one of the player's `item` to `vendor`
+pub fn sell_item(ecs: &mut World, vendor: Entity, item: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let vendor_name = ecs
+        .read_storage::<Name>()
+        .get(vendor)
+        .map_or("shopkeeper".to_string(), |n| n.name.clone());
+    let price = match sell_price(ecs, item) {
+        Some(price) => price,
+        None => {
+            let name = describe_item(ecs, item);
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push(format!("The {} doesn't want the {}.", vendor_name, name));
+            return;
+        }
+    };
+
+    let item = take_one(ecs, item);
+    let name = describe_item(ecs, item);
+    shift_load(ecs, player_entity, item, -1.0);
+    hand_over(ecs, item, vendor);
+    if let Some(purse) = ecs.write_storage::<Purse>().get_mut(player_entity) {
+        purse.gold += price;
+    }
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("You sell the {} for {} gold.", name, price));
+}
+
+/// Takes a single item off a stack, or the item itself if it's alone
+fn take_one(ecs: &mut World, item: Entity) -> Entity {
+    let quantity = ecs
+        .read_storage::<Stackable>()
+        .get(item)
+        .map_or(1, |s| s.quantity);
+    if quantity > 1 {
+        split_stack(ecs, item, 1)
+    } else {
+        item
+    }
+}
+
+/// Adds or takes `item`'s weight off what `owner` carries, so a shopping
+/// spree is weighed before the next turn comes around
+pub fn shift_load(ecs: &mut World, owner: Entity, item: Entity, sign: f32) {
+    let weight = stack_weight(
+        ecs.read_storage::<Weight>().get(item),
+        ecs.read_storage::<Stackable>().get(item),
+    );
+    if let Some(encumbrance) = ecs.write_storage::<Encumbrance>().get_mut(owner) {
+        encumbrance.load = f32::max(0.0, encumbrance.load + weight * sign);
+    }
+}
+
+/// Puts `item` in `owner`'s backpack, joining a stack of the same thing if
+/// they carry one
+pub fn hand_over(ecs: &mut World, item: Entity, owner: Entity) {
+    let stack = {
+        let entities = ecs.entities();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let names = ecs.read_storage::<Name>();
+        let stackables = ecs.read_storage::<Stackable>();
+        let item_name = names.get(item).map(|n| n.name.clone());
+        if stackables.get(item).is_some() {
+            (&entities, &backpack, &names, &stackables)
+                .join()
+                .find(|(stack, pack, name, _)| {
+                    *stack != item && pack.owner == owner && Some(&name.name) == item_name.as_ref()
+                })
+                .map(|(stack, _, _, _)| stack)
+        } else {
+            None
+        }
+    };
+
+    match stack {
+        Some(stack) => {
+            let quantity = ecs
+                .read_storage::<Stackable>()
+                .get(item)
+                .map_or(1, |s| s.quantity);
+            if let Some(stack) = ecs.write_storage::<Stackable>().get_mut(stack) {
+                stack.quantity += quantity;
+            }
+            ecs.delete_entity(item).expect("Unable to delete");
+        }
+        None => {
+            ecs.write_storage::<InBackpack>()
+                .insert(item, InBackpack { owner })
+                .expect("Unable to insert backpack entry");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawner, test_support::test_world};
+
+    /// Spawns the item called `name` in `owner`'s backpack, with no
+    /// enchantment whatever the dice said
+    fn spawn_for(ecs: &mut World, owner: Entity, name: &str) -> Entity {
+        let item = spawner::spawn_named(ecs, name, 1, 1).unwrap();
+        ecs.write_storage::<Enchantment>().remove(item);
+        spawner::give_item(ecs, owner, item);
+        item
+    }
+
+    fn set_gold(ecs: &mut World, gold: i32) {
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Purse>().get_mut(player).unwrap().gold = gold;
+    }
+
+    fn gold(ecs: &World) -> i32 {
+        let player = *ecs.fetch::<Entity>();
+        ecs.read_storage::<Purse>().get(player).unwrap().gold
+    }
+
+    fn quantity(ecs: &World, item: Entity) -> i32 {
+        ecs.read_storage::<Stackable>().get(item).unwrap().quantity
+    }
+
+    fn enchant(ecs: &mut World, item: Entity, bonus: i32) {
+        ecs.write_storage::<Enchantment>()
+            .insert(item, Enchantment { bonus, known: true })
+            .unwrap();
+    }
+
+    #[test]
+    fn enchantment_changes_an_items_worth_by_quarters() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let dagger = spawn_for(&mut ecs, player, "Dagger");
+        assert_eq!(item_worth(&ecs, dagger), Some(10));
+        enchant(&mut ecs, dagger, 2);
+        assert_eq!(item_worth(&ecs, dagger), Some(15));
+        enchant(&mut ecs, dagger, -4);
+        assert_eq!(item_worth(&ecs, dagger), Some(1));
+    }
+
+    #[test]
+    fn shops_charge_more_the_deeper_they_are_and_pay_a_third() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let dagger = spawn_for(&mut ecs, player, "Dagger");
+        assert_eq!(buy_price(&ecs, dagger), Some(11));
+        ecs.write_resource::<Map>().depth = 10;
+        assert_eq!(buy_price(&ecs, dagger), Some(20));
+        assert_eq!(sell_price(&ecs, dagger), Some(3));
+
+        let meat = spawn_for(&mut ecs, player, "Rat Meat");
+        assert_eq!(sell_price(&ecs, meat), Some(1));
+    }
+
+    #[test]
+    fn items_without_value_cant_be_traded() {
+        let mut ecs = test_world();
+        let coins = spawner::spawn_named(&mut ecs, "Gold", 1, 1).unwrap();
+        assert_eq!(buy_price(&ecs, coins), None);
+        assert_eq!(sell_price(&ecs, coins), None);
+    }
+
+    #[test]
+    fn buying_takes_one_off_the_shelf_for_its_price() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let vendor = spawner::spawn_named(&mut ecs, "Shopkeeper", 2, 2).unwrap();
+        let potions = spawn_for(&mut ecs, vendor, "Health Potion");
+        ecs.write_storage::<Stackable>()
+            .get_mut(potions)
+            .unwrap()
+            .quantity = 2;
+        let price = buy_price(&ecs, potions).unwrap();
+        set_gold(&mut ecs, price + 5);
+
+        buy_item(&mut ecs, vendor, potions);
+        ecs.maintain();
+        assert_eq!(gold(&ecs), 5);
+        assert_eq!(quantity(&ecs, potions), 1);
+        let bought: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<InBackpack>())
+            .join()
+            .filter(|(_, pack)| pack.owner == player)
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(bought.len(), 1);
+        assert_eq!(quantity(&ecs, bought[0]), 1);
+        assert!(ecs.read_storage::<Encumbrance>().get(player).unwrap().load > 0.0);
+    }
+
+    #[test]
+    fn nothing_is_sold_to_a_player_who_cant_pay() {
+        let mut ecs = test_world();
+        let vendor = spawner::spawn_named(&mut ecs, "Shopkeeper", 2, 2).unwrap();
+        let dagger = spawn_for(&mut ecs, vendor, "Dagger");
+        set_gold(&mut ecs, 3);
+
+        buy_item(&mut ecs, vendor, dagger);
+        assert_eq!(gold(&ecs), 3);
+        assert_eq!(
+            ecs.read_storage::<InBackpack>().get(dagger).unwrap().owner,
+            vendor
+        );
+    }
+
+    #[test]
+    fn selling_joins_the_shopkeepers_stack() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let vendor = spawner::spawn_named(&mut ecs, "Shopkeeper", 2, 2).unwrap();
+        let theirs = spawn_for(&mut ecs, vendor, "Rations");
+        let mine = spawn_for(&mut ecs, player, "Rations");
+        ecs.write_storage::<Stackable>()
+            .get_mut(mine)
+            .unwrap()
+            .quantity = 3;
+        let price = sell_price(&ecs, mine).unwrap();
+
+        sell_item(&mut ecs, vendor, mine);
+        ecs.maintain();
+        assert_eq!(gold(&ecs), price);
+        assert_eq!(quantity(&ecs, mine), 2);
+        assert_eq!(quantity(&ecs, theirs), 2);
+    }
+}
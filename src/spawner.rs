@@ -1,16 +1,19 @@
 use crate::RevealChance;
 
 use crate::{
-    random_table::*, AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable, DefenseBonus,
-    EntryTrigger, EquipmentSlot, Equippable, Hidden, HungerClock, HungerState, InflictsDamage,
-    Item, MagicMapper, Map, MeleePowerBonus, MonsterAI, Name, PeriodicHiding, Player, Position,
-    ProvidesFood, ProvidesHealing, Ranged, Rect, Renderable, SerializeMe, SimpleMarker,
-    SingleActivation, TileType, Viewshed, MAP_WIDTH,
+    random_table::*, AreaOfEffect, Awareness, AwarenessState, BlocksTile, Charges, CombatStats,
+    Confusion, Consumable, Cursed, DefenseBonus, Enchantment, Encumbrance, EncumbranceLevel,
+    EntryTrigger, EquipmentSlot, Equippable, Faction, Gold, Hidden, HungerClock, HungerState,
+    IdentifiesItems, InBackpack, InflictsDamage, Item, ItemCollector, ItemIdentities, LightSource,
+    MagicMapper, Map, MeleePowerBonus, MonsterAI, Name, OffHand, PeriodicHiding, Player, Position,
+    ProvidesFood, ProvidesHealing, Purse, Ranged, Rect, Regeneration, RemovesCurse, Renderable,
+    SerializeMe, SimpleMarker, SingleActivation, Speed, SpellCaster, Stackable, Stealth,
+    Sustenance, TileType, TwoHanded, Value, Vendor, Viewshed, Weight,
 };
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
 use specs::saveload::MarkedBuilder;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 const MAX_MONSTERS: i32 = 4;
 
@@ -18,6 +21,8 @@ fn room_table(map_depth: i32) -> RandomTable {
     RandomTable::new()
         .add("Goblin", 10)
         .add("Orc", 1 + map_depth)
+        .add("Rat", 4)
+        .add("Goblin Shaman", map_depth)
         .add("Health Potion", 7)
         .add("Fireball Scroll", 2 + map_depth)
         .add("Confusion Scroll", 2 + map_depth)
@@ -26,8 +31,25 @@ fn room_table(map_depth: i32) -> RandomTable {
         .add("Shield", 3)
         .add("Longsword", map_depth - 1)
         .add("Tower Shield", map_depth - 1)
+        .add("Short Sword", 2)
+        .add("Greatsword", map_depth - 2)
         .add("Rations", 10)
+        .add("Healing Herb", 6)
+        .add("Whetstone", 2)
         .add("Magic Mapping Scroll", 2)
+        .add("Identify Scroll", 3)
+        .add("Remove Curse Scroll", 2)
+        .add("Wand of Magic Missile", map_depth)
+        .add("Staff of Fireball", map_depth - 2)
+        .add("Leather Armor", 3)
+        .add("Chain Mail", map_depth - 1)
+        .add("Iron Helm", 2)
+        .add("Leather Gloves", 2)
+        .add("Leather Boots", 2)
+        .add("Boots of Speed", map_depth - 3)
+        .add("Ring of Regeneration", map_depth - 2)
+        .add("Amulet of Sustenance", map_depth - 2)
+        .add("Gold", 6)
         .add("Bear Trap", 3)
         .add("Periodic Trap", 4)
 }
@@ -46,20 +68,44 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             render_order: 0,
         })
         .with(Player {})
+        // Only lit tiles can be seen, so in the dark the player's own light
+        // decides how far they see. The view reaches well past that so rooms
+        // lit by braziers show up from across the dark
         .with(Viewshed {
             visible_tiles: Vec::new(),
-            range: 8,
+            range: 16,
             dirty: true,
         })
+        .with(LightSource {
+            color: RGB::from_f32(1.0, 0.85, 0.6),
+            range: 6,
+        })
         .with(Name {
             name: "Player".to_string(),
         })
+        .with(Faction {
+            name: "Player".to_string(),
+        })
+        .with(Awareness {
+            state: AwarenessState::Aware,
+            perception: 2,
+        })
+        .with(Stealth {
+            skill: 2,
+            sneaking: false,
+        })
         .with(CombatStats {
             max_hp: 30,
             hp: 30,
             defense: 2,
             power: 5,
         })
+        .with(Encumbrance {
+            load: 0.0,
+            capacity: 0.0,
+            level: EncumbranceLevel::Unencumbered,
+        })
+        .with(Purse { gold: 0 })
         .marked::<SimpleMarker<SerializeMe>>()
         .with(HungerClock {
             state: HungerState::WellFed,
@@ -68,16 +114,71 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
         .build()
 }
 
-fn orc(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('o'), "Orc");
+fn orc(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('o'), "Orc", "Orcs")
 }
 
-fn goblin(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin")
+fn goblin(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let goblin = monster(ecs, x, y, rltk::to_cp437('g'), "Goblin", "Goblins");
+    ecs.write_storage::<ItemCollector>()
+        .insert(goblin, ItemCollector {})
+        .expect("Unable to insert ItemCollector");
+    goblin
 }
 
-fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharType, name: S) {
-    ecs.create_entity()
+/// Spawns a goblin that reads scrolls at its enemies, carrying one to start
+fn goblin_shaman(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let shaman = monster(ecs, x, y, rltk::to_cp437('g'), "Goblin Shaman", "Goblins");
+    ecs.write_storage::<ItemCollector>()
+        .insert(shaman, ItemCollector {})
+        .expect("Unable to insert ItemCollector");
+    ecs.write_storage::<SpellCaster>()
+        .insert(shaman, SpellCaster {})
+        .expect("Unable to insert SpellCaster");
+    ecs.write_storage::<LightSource>()
+        .insert(
+            shaman,
+            LightSource {
+                color: RGB::named(rltk::GREEN),
+                range: 3,
+            },
+        )
+        .expect("Unable to insert LightSource");
+
+    let scroll = magic_missile_scroll(ecs, x, y);
+    give_item(ecs, shaman, scroll);
+    shaman
+}
+
+/// Moves a freshly spawned `item` off the floor and into `owner`'s backpack
+pub(crate) fn give_item(ecs: &mut World, owner: Entity, item: Entity) {
+    ecs.write_storage::<Position>().remove(item);
+    ecs.write_storage::<InBackpack>()
+        .insert(item, InBackpack { owner })
+        .expect("Unable to insert backpack entry");
+}
+
+fn monster<S: ToString>(
+    ecs: &mut World,
+    x: i32,
+    y: i32,
+    glyph: rltk::FontCharType,
+    name: S,
+    faction: S,
+) -> Entity {
+    // Some monsters are found sleeping
+    let state = if ecs
+        .write_resource::<RandomNumberGenerator>()
+        .roll_dice(1, 3)
+        == 1
+    {
+        AwarenessState::Asleep
+    } else {
+        AwarenessState::Unaware
+    };
+
+    let monster = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph,
@@ -94,6 +195,13 @@ fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharTy
         .with(Name {
             name: name.to_string(),
         })
+        .with(Faction {
+            name: faction.to_string(),
+        })
+        .with(Awareness {
+            state,
+            perception: 0,
+        })
         .with(BlocksTile {})
         .with(CombatStats {
             max_hp: 16,
@@ -103,6 +211,80 @@ fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharTy
         })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+
+    // Half of them have some coins on them, dropped when they die
+    let has_gold = ecs
+        .write_resource::<RandomNumberGenerator>()
+        .roll_dice(1, 2)
+        == 1;
+    if has_gold {
+        let coins = gold(ecs, x, y);
+        give_item(ecs, monster, coins);
+    }
+    monster
+}
+
+/// Spawns a pile of gold, bigger the deeper it's found
+fn gold(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let amount = {
+        let depth = ecs.fetch::<Map>().depth;
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(2, 6) + depth * 3
+    };
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('$'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Gold".to_string(),
+        })
+        .with(Item {})
+        .with(Gold { amount })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a harmless critter that runs away from most things
+fn rat(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let rat = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('r'),
+            fg: RGB::named(rltk::BURLYWOOD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 1,
+        })
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: 6,
+            dirty: true,
+        })
+        .with(MonsterAI::new())
+        .with(Name {
+            name: "Rat".to_string(),
+        })
+        .with(Faction {
+            name: "Critters".to_string(),
+        })
+        .with(BlocksTile {})
+        .with(CombatStats {
+            max_hp: 4,
+            hp: 4,
+            defense: 0,
+            power: 1,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    // Whoever kills it gets something to cook
+    let meat = rat_meat(ecs, x, y);
+    give_item(ecs, rat, meat);
+    rat
 }
 
 /// Fills a room with stuff!
@@ -122,16 +304,137 @@ pub fn fill_room(ecs: &mut World, room: &Rect, map_depth: i32) {
         }
     }
 
+    // Some rooms are lit by a brazier standing somewhere in them
+    let brazier_spot = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        if rng.roll_dice(1, 2) == 1 && !possible_targets.is_empty() {
+            Some((rng.roll_dice(1, possible_targets.len() as i32) - 1) as usize)
+        } else {
+            None
+        }
+    };
+    if let Some(spot) = brazier_spot {
+        let idx = possible_targets.remove(spot);
+        let width = ecs.fetch::<Map>().width;
+        brazier(ecs, idx as i32 % width, idx as i32 / width);
+    }
+
     // Fill floor tiles with stuff
     fill_region(ecs, &possible_targets, map_depth);
 }
 
+/// What shops can have for sale
+const SHOP_STOCK: [&str; 17] = [
+    "Health Potion",
+    "Rations",
+    "Healing Herb",
+    "Whetstone",
+    "Magic Missile Scroll",
+    "Identify Scroll",
+    "Remove Curse Scroll",
+    "Magic Mapping Scroll",
+    "Dagger",
+    "Short Sword",
+    "Longsword",
+    "Shield",
+    "Leather Armor",
+    "Iron Helm",
+    "Leather Gloves",
+    "Leather Boots",
+    "Wand of Magic Missile",
+];
+
+/// Turns `room` into a lit shop, with a shopkeeper in the middle carrying
+/// their wares instead of the usual monsters and items
+pub fn shop_room(ecs: &mut World, room: &Rect) {
+    let (x, y) = room.center();
+    let floor = {
+        let map = ecs.fetch::<Map>();
+        map.tiles[map.xy_idx(x, y)] == TileType::Floor
+    };
+    if !floor {
+        return;
+    }
+    brazier(ecs, room.x1 + 1, room.y1 + 1);
+    let keeper = shopkeeper(ecs, x, y);
+
+    let wares = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        (0..rng.range(5, 9))
+            .map(|_| SHOP_STOCK[rng.range(0, SHOP_STOCK.len() as i32) as usize])
+            .collect::<Vec<&str>>()
+    };
+    for name in wares {
+        if let Some(item) = spawn_named(ecs, name, x, y) {
+            give_item(ecs, keeper, item);
+        }
+    }
+}
+
+/// Spawns a shopkeeper, who minds their own business unless attacked
+fn shopkeeper(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::LIGHT_GREEN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 1,
+        })
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: 8,
+            dirty: true,
+        })
+        .with(Name {
+            name: "Shopkeeper".to_string(),
+        })
+        .with(Faction {
+            name: "Shopkeepers".to_string(),
+        })
+        .with(Awareness {
+            state: AwarenessState::Aware,
+            perception: 2,
+        })
+        .with(Vendor {})
+        .with(BlocksTile {})
+        .with(CombatStats {
+            max_hp: 60,
+            hp: 60,
+            defense: 4,
+            power: 10,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a brazier that lights up the room around it
+fn brazier(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('☼'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Brazier".to_string(),
+        })
+        .with(LightSource {
+            color: RGB::from_f32(1.0, 0.6, 0.2),
+            range: 8,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
 /// Fills a region `area` with stuff
 pub fn fill_region(ecs: &mut World, area: &[usize], map_depth: i32) {
     // Get map's spawn table
     let spawn_table = room_table(map_depth);
     // Keep a map of what we've decided to spawn
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+    let mut spawn_points: BTreeMap<usize, String> = BTreeMap::new();
     // Clone `area` to avoid mutation
     let mut areas: Vec<usize> = Vec::from(area);
 
@@ -172,35 +475,111 @@ pub fn fill_region(ecs: &mut World, area: &[usize], map_depth: i32) {
 
 /// Spawns a named entity (name in tuple.1) at the location in (tuple.0)
 fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
-    let x = (*spawn.0 % MAP_WIDTH) as i32;
-    let y = (*spawn.0 / MAP_WIDTH) as i32;
-
-    match spawn.1.as_ref() {
-        "Goblin" => goblin(ecs, x, y),
-        "Orc" => orc(ecs, x, y),
-        "Health Potion" => health_potion(ecs, x, y),
-        "Fireball Scroll" => fireball_scroll(ecs, x, y),
-        "Confusion Scroll" => confusion_scroll(ecs, x, y),
-        "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
-        "Dagger" => dagger(ecs, x, y),
-        "Shield" => shield(ecs, x, y),
-        "Longsword" => longsword(ecs, x, y),
-        "Tower Shield" => tower_shield(ecs, x, y),
-        "Rations" => rations(ecs, x, y),
-        "Magic Mapping Scroll" => magic_mapping_scroll(ecs, x, y),
-        "Bear Trap" => bear_trap(ecs, x, y),
-        "Periodic Trap" => periodic_trap(ecs, x, y),
-        _ => {}
+    let map_width = ecs.fetch::<Map>().width as usize;
+    let x = (*spawn.0 % map_width) as i32;
+    let y = (*spawn.0 / map_width) as i32;
+    spawn_named(ecs, spawn.1, x, y);
+}
+
+/// Every name `spawn_named` knows
+pub const SPAWN_NAMES: [&str; 36] = [
+    "Goblin",
+    "Orc",
+    "Rat",
+    "Goblin Shaman",
+    "Health Potion",
+    "Fireball Scroll",
+    "Confusion Scroll",
+    "Magic Missile Scroll",
+    "Dagger",
+    "Shield",
+    "Longsword",
+    "Tower Shield",
+    "Short Sword",
+    "Greatsword",
+    "Rations",
+    "Healing Herb",
+    "Rat Meat",
+    "Whetstone",
+    "Magic Mapping Scroll",
+    "Identify Scroll",
+    "Remove Curse Scroll",
+    "Wand of Magic Missile",
+    "Staff of Fireball",
+    "Leather Armor",
+    "Chain Mail",
+    "Iron Helm",
+    "Leather Gloves",
+    "Leather Boots",
+    "Boots of Speed",
+    "Ring of Regeneration",
+    "Amulet of Sustenance",
+    "Gold",
+    "Shopkeeper",
+    "Brazier",
+    "Bear Trap",
+    "Periodic Trap",
+];
+
+/// Spawns the entity called `name` at `x`, `y`, returning it. Unknown names
+/// spawn nothing
+pub fn spawn_named(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    match name {
+        "Goblin" => Some(goblin(ecs, x, y)),
+        "Orc" => Some(orc(ecs, x, y)),
+        "Rat" => Some(rat(ecs, x, y)),
+        "Goblin Shaman" => Some(goblin_shaman(ecs, x, y)),
+        "Health Potion" => Some(health_potion(ecs, x, y)),
+        "Fireball Scroll" => Some(fireball_scroll(ecs, x, y)),
+        "Confusion Scroll" => Some(confusion_scroll(ecs, x, y)),
+        "Magic Missile Scroll" => Some(magic_missile_scroll(ecs, x, y)),
+        "Dagger" => Some(dagger(ecs, x, y)),
+        "Shield" => Some(shield(ecs, x, y)),
+        "Longsword" => Some(longsword(ecs, x, y)),
+        "Tower Shield" => Some(tower_shield(ecs, x, y)),
+        "Short Sword" => Some(short_sword(ecs, x, y)),
+        "Greatsword" => Some(greatsword(ecs, x, y)),
+        "Rations" => Some(rations(ecs, x, y)),
+        "Healing Herb" => Some(healing_herb(ecs, x, y)),
+        "Rat Meat" => Some(rat_meat(ecs, x, y)),
+        "Whetstone" => Some(whetstone(ecs, x, y)),
+        "Magic Mapping Scroll" => Some(magic_mapping_scroll(ecs, x, y)),
+        "Identify Scroll" => Some(identify_scroll(ecs, x, y)),
+        "Remove Curse Scroll" => Some(remove_curse_scroll(ecs, x, y)),
+        "Wand of Magic Missile" => Some(magic_missile_wand(ecs, x, y)),
+        "Staff of Fireball" => Some(fireball_staff(ecs, x, y)),
+        "Leather Armor" => Some(leather_armor(ecs, x, y)),
+        "Chain Mail" => Some(chain_mail(ecs, x, y)),
+        "Iron Helm" => Some(iron_helm(ecs, x, y)),
+        "Leather Gloves" => Some(leather_gloves(ecs, x, y)),
+        "Leather Boots" => Some(leather_boots(ecs, x, y)),
+        "Boots of Speed" => Some(boots_of_speed(ecs, x, y)),
+        "Ring of Regeneration" => Some(ring_of_regeneration(ecs, x, y)),
+        "Amulet of Sustenance" => Some(amulet_of_sustenance(ecs, x, y)),
+        "Gold" => Some(gold(ecs, x, y)),
+        "Shopkeeper" => Some(shopkeeper(ecs, x, y)),
+        "Brazier" => Some(brazier(ecs, x, y)),
+        "Bear Trap" => Some(bear_trap(ecs, x, y)),
+        "Periodic Trap" => Some(periodic_trap(ecs, x, y)),
+        _ => None,
     }
 }
 
+/// The colour the potion or scroll called `name` wears this run
+fn disguise_color(ecs: &World, name: &str) -> RGB {
+    ecs.fetch::<ItemIdentities>()
+        .color(name)
+        .unwrap_or_else(|| RGB::named(rltk::WHITE))
+}
+
 /// Spawns a health potion into the world at given x, y location
-fn health_potion(ecs: &mut World, x: i32, y: i32) {
+fn health_potion(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Health Potion");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('¡'),
-            fg: RGB::named(rltk::MAGENTA),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
@@ -208,19 +587,23 @@ fn health_potion(ecs: &mut World, x: i32, y: i32) {
             name: "Health Potion".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 50 })
+        .with(Stackable { quantity: 1 })
         .with(Consumable {})
         .with(ProvidesHealing { heal_amount: 8 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
 /// Spawns a magic missile scroll at given x, y location
-fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
+fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Magic Missile Scroll");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::CYAN),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
@@ -228,19 +611,23 @@ fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Magic Missile Scroll".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 50 })
+        .with(Stackable { quantity: 1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 8 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
+fn fireball_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Fireball Scroll");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::ORANGE),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
@@ -248,20 +635,24 @@ fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Fireball Scroll".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 100 })
+        .with(Stackable { quantity: 1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 20 })
         .with(AreaOfEffect { radius: 3 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
+fn confusion_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Confusion Scroll");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::PINK),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
@@ -269,15 +660,68 @@ fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Confusion Scroll".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 60 })
+        .with(Stackable { quantity: 1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(Confusion { turns: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn dagger(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
+/// Rolls how enchanted a piece of equipment found at `depth` is. Deeper
+/// levels hand out more enchantments and bigger ones, a third of them
+/// negative
+fn roll_enchantment(rng: &mut RandomNumberGenerator, depth: i32) -> i32 {
+    if rng.roll_dice(1, 100) > 20 + depth * 5 {
+        return 0;
+    }
+    let size = rng.range(1, i32::min(3, 1 + depth / 2) + 1);
+    if rng.roll_dice(1, 3) == 1 {
+        -size
+    } else {
+        size
+    }
+}
+
+/// Gives a freshly spawned piece of equipment a random enchantment, cursing
+/// it if the enchantment is negative
+fn enchant(ecs: &mut World, item: Entity) {
+    let bonus = {
+        let depth = ecs.fetch::<Map>().depth;
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        roll_enchantment(&mut rng, depth)
+    };
+    if bonus == 0 {
+        return;
+    }
+
+    if let Some(melee) = ecs.write_storage::<MeleePowerBonus>().get_mut(item) {
+        melee.power += bonus;
+    }
+    if let Some(defense) = ecs.write_storage::<DefenseBonus>().get_mut(item) {
+        defense.defense += bonus;
+    }
+    ecs.write_storage::<Enchantment>()
+        .insert(
+            item,
+            Enchantment {
+                bonus,
+                known: false,
+            },
+        )
+        .expect("Unable to insert enchantment");
+    if bonus < 0 {
+        ecs.write_storage::<Cursed>()
+            .insert(item, Cursed {})
+            .expect("Unable to insert curse");
+    }
+}
+
+fn dagger(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('/'),
@@ -289,16 +733,22 @@ fn dagger(ecs: &mut World, x: i32, y: i32) {
             name: "Dagger".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 1.0 })
+        .with(Value { value: 10 })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 2 })
+        .with(OffHand {})
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+    enchant(ecs, item);
+    item
 }
 
-fn shield(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
+fn shield(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('('),
@@ -310,16 +760,21 @@ fn shield(ecs: &mut World, x: i32, y: i32) {
             name: "Shield".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 6.0 })
+        .with(Value { value: 20 })
         .with(DefenseBonus { defense: 1 })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+    enchant(ecs, item);
+    item
 }
 
-fn longsword(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
+fn longsword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('/'),
@@ -331,16 +786,75 @@ fn longsword(ecs: &mut World, x: i32, y: i32) {
             name: "Longsword".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 3.0 })
+        .with(Value { value: 40 })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+    enchant(ecs, item);
+    item
 }
 
-fn tower_shield(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
+fn short_sword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Short Sword".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 2.0 })
+        .with(Value { value: 25 })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+        })
+        .with(MeleePowerBonus { power: 3 })
+        .with(OffHand {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn greatsword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Greatsword".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 12.0 })
+        .with(Value { value: 120 })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+        })
+        .with(MeleePowerBonus { power: 7 })
+        .with(TwoHanded {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn tower_shield(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('('),
@@ -352,15 +866,218 @@ fn tower_shield(ecs: &mut World, x: i32, y: i32) {
             name: "Tower Shield".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 15.0 })
+        .with(Value { value: 60 })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .with(DefenseBonus { defense: 3 })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+    enchant(ecs, item);
+    item
+}
+
+fn leather_armor(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Leather Armor".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 10.0 })
+        .with(Value { value: 30 })
+        .with(Equippable {
+            slot: EquipmentSlot::Body,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn chain_mail(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Chain Mail".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 25.0 })
+        .with(Value { value: 100 })
+        .with(Equippable {
+            slot: EquipmentSlot::Body,
+        })
+        .with(DefenseBonus { defense: 3 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn iron_helm(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Iron Helm".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 4.0 })
+        .with(Value { value: 25 })
+        .with(Equippable {
+            slot: EquipmentSlot::Head,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn leather_gloves(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::BURLYWOOD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Leather Gloves".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 1.0 })
+        .with(Value { value: 15 })
+        .with(Equippable {
+            slot: EquipmentSlot::Hands,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn leather_boots(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::BURLYWOOD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Leather Boots".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 2.0 })
+        .with(Value { value: 15 })
+        .with(Equippable {
+            slot: EquipmentSlot::Feet,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    enchant(ecs, item);
+    item
+}
+
+fn boots_of_speed(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Boots of Speed".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 2.0 })
+        .with(Value { value: 300 })
+        .with(Equippable {
+            slot: EquipmentSlot::Feet,
+        })
+        .with(Speed { interval: 3 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn ring_of_regeneration(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('='),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Ring of Regeneration".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.1 })
+        .with(Value { value: 300 })
+        .with(Equippable {
+            slot: EquipmentSlot::LeftRing,
+        })
+        .with(Regeneration { interval: 5 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn rations(ecs: &mut World, x: i32, y: i32) {
+fn amulet_of_sustenance(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('"'),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Amulet of Sustenance".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.2 })
+        .with(Value { value: 250 })
+        .with(Equippable {
+            slot: EquipmentSlot::Amulet,
+        })
+        .with(Sustenance {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn rations(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -373,18 +1090,86 @@ fn rations(ecs: &mut World, x: i32, y: i32) {
             name: "Rations".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 2.0 })
+        .with(Value { value: 10 })
+        .with(Stackable { quantity: 1 })
         .with(ProvidesFood {})
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+/// Spawns a herb, no use by itself but brewed into health potions
+fn healing_herb(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('♣'),
+            fg: RGB::named(rltk::LIGHT_GREEN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Healing Herb".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.1 })
+        .with(Value { value: 8 })
+        .with(Stackable { quantity: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a raw cut of rat, which needs cooking into rations before it's fit
+/// to eat
+fn rat_meat(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::SALMON),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Rat Meat".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 2 })
+        .with(Stackable { quantity: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
+/// Spawns a whetstone, which puts an edge on a melee weapon
+fn whetstone(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('*'),
+            fg: RGB::named(rltk::GRAY),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Whetstone".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 1.0 })
+        .with(Value { value: 15 })
+        .with(Stackable { quantity: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Scroll of Magic Mapping");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::CYAN3),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
@@ -392,13 +1177,113 @@ fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Scroll of Magic Mapping".to_string(),
         })
         .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 80 })
+        .with(Stackable { quantity: 1 })
         .with(MagicMapper {})
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+/// Spawns a scroll that tells the reader what everything they carry is
+fn identify_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Identify Scroll");
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg,
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Identify Scroll".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 40 })
+        .with(Stackable { quantity: 1 })
+        .with(IdentifiesItems {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a scroll that lifts the curses from the reader's gear
+fn remove_curse_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let fg = disguise_color(ecs, "Remove Curse Scroll");
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg,
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Remove Curse Scroll".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 0.5 })
+        .with(Value { value: 80 })
+        .with(Stackable { quantity: 1 })
+        .with(RemovesCurse {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn bear_trap(ecs: &mut World, x: i32, y: i32) {
+/// Spawns a wand that fires magic missiles until its charges run out
+fn magic_missile_wand(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let charges = ecs.write_resource::<RandomNumberGenerator>().range(3, 7);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('-'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Wand of Magic Missile".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 1.0 })
+        .with(Value { value: 150 })
+        .with(Charges { charges })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 8 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a staff that casts a few fireballs before crumbling
+fn fireball_staff(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let charges = ecs.write_resource::<RandomNumberGenerator>().range(2, 4);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('|'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Staff of Fireball".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { weight: 5.0 })
+        .with(Value { value: 250 })
+        .with(Charges { charges })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 20 })
+        .with(AreaOfEffect { radius: 3 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn bear_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -416,10 +1301,10 @@ fn bear_trap(ecs: &mut World, x: i32, y: i32) {
         .with(SingleActivation {})
         .with(RevealChance { chance: 36 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn periodic_trap(ecs: &mut World, x: i32, y: i32) {
+fn periodic_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
     let rand_offset;
     {
         rand_offset = ecs
@@ -446,5 +1331,5 @@ fn periodic_trap(ecs: &mut World, x: i32, y: i32) {
             offset: rand_offset,
         })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
@@ -0,0 +1,56 @@
+use super::{save_json, storage_location};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Running totals for the current game, fed by the systems as things happen
+/// and saved along with it. Anything missing from an older save starts at
+/// zero
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RunStats {
+    /// The seed the game's levels and dice were started from
+    pub seed: u64,
+    pub turns: i32,
+    /// Turns the player spent on each depth
+    pub turns_per_depth: BTreeMap<i32, i32>,
+    /// Monsters the player has killed, by name
+    pub kills: BTreeMap<String, i32>,
+    /// Damage the player has dealt, by its source
+    pub damage_dealt: BTreeMap<String, i32>,
+    /// Damage the player has taken, by its source
+    pub damage_received: BTreeMap<String, i32>,
+    /// Items the player has picked up, by name
+    pub items_found: BTreeMap<String, i32>,
+    /// Items the player has used, by name
+    pub items_used: BTreeMap<String, i32>,
+    /// Healing potions the player drank while already at full health
+    pub potions_wasted: i32,
+    /// Traps the player has set off, by name
+    pub traps_triggered: BTreeMap<String, i32>,
+    /// Hidden things the player has spotted, by name
+    pub traps_spotted: BTreeMap<String, i32>,
+    /// How many times the player has become each hunger state
+    pub hunger_states: BTreeMap<String, i32>,
+    pub cause_of_death: Option<String>,
+}
+
+impl RunStats {
+    pub fn new(seed: u64) -> RunStats {
+        RunStats {
+            seed,
+            ..Default::default()
+        }
+    }
+}
+
+/// Picks a seed to start a new game from
+pub fn new_seed() -> u64 {
+    rltk::RandomNumberGenerator::new().next_u64()
+}
+
+/// Writes the statistics out as JSON for analysis, returning where they went
+pub fn export_stats(stats: &RunStats) -> Option<String> {
+    let key = format!("stats-{}", stats.seed);
+    save_json(&key, stats).ok()?;
+    Some(storage_location(&key))
+}
@@ -0,0 +1,115 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::read_to_string;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// Why something couldn't be saved
+#[derive(Debug)]
+pub enum StorageError {
+    /// It couldn't be written out as JSON
+    Serialize(serde_json::Error),
+    /// There was nowhere to put it, or putting it there failed
+    Write(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::Serialize(e) => write!(f, "couldn't serialize: {}", e),
+            StorageError::Write(e) => write!(f, "couldn't write: {}", e),
+        }
+    }
+}
+
+/// Where something saved under `key` lives on disk
+#[cfg(not(target_arch = "wasm32"))]
+fn storage_path(key: &str) -> String {
+    format!("./{}.json", key)
+}
+
+/// Where something saved under `key` can be found, for telling the player
+#[cfg(not(target_arch = "wasm32"))]
+pub fn storage_location(key: &str) -> String {
+    storage_path(key)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn storage_location(key: &str) -> String {
+    format!("local storage (\"{}\")", key)
+}
+
+/// The browser's local storage, if it has one we're allowed to use
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Reads back the text saved under `key`. On the desktop that's a JSON file
+/// next to the game, in the browser local storage
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_text(key: &str) -> Option<String> {
+    read_to_string(storage_path(key)).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load_text(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Saves `text` under `key`, replacing whatever was there
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_text(key: &str, text: &str) -> Result<(), StorageError> {
+    std::fs::write(storage_path(key), text).map_err(|e| StorageError::Write(e.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_text(key: &str, text: &str) -> Result<(), StorageError> {
+    match local_storage() {
+        Some(store) => store
+            .set_item(key, text)
+            .map_err(|_| StorageError::Write("local storage is full".to_string())),
+        None => Err(StorageError::Write("no local storage".to_string())),
+    }
+}
+
+/// Whether anything is saved under `key`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn is_saved(key: &str) -> bool {
+    Path::new(&storage_path(key)).exists()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn is_saved(key: &str) -> bool {
+    load_text(key).is_some()
+}
+
+/// Forgets whatever was saved under `key`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn delete_saved(key: &str) {
+    if is_saved(key) {
+        if let Err(e) = std::fs::remove_file(storage_path(key)) {
+            rltk::console::log(format!("Unable to delete {}: {}", key, e));
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn delete_saved(key: &str) {
+    if let Some(store) = local_storage() {
+        let _ = store.remove_item(key);
+    }
+}
+
+/// Reads back the value saved under `key`, if there's anything usable
+pub fn load_json<T: DeserializeOwned>(key: &str) -> Option<T> {
+    serde_json::from_str(&load_text(key)?).ok()
+}
+
+/// Saves `value` under `key` as JSON, replacing whatever was there
+pub fn save_json<T: Serialize>(key: &str, value: &T) -> Result<(), StorageError> {
+    let output = serde_json::to_string_pretty(value).map_err(StorageError::Serialize)?;
+    save_text(key, &output)
+}
@@ -0,0 +1,40 @@
+use super::{
+    gamelog::GameLog, register_storages, spawner, Encumbrance, ItemIdentities, Map, RunStats,
+    SerializeMe, TileType,
+};
+use rltk::Point;
+use specs::prelude::*;
+use specs::saveload::SimpleMarkerAllocator;
+
+/// A small world with every storage registered, a walled 10x10 map whose
+/// inside is visible floor, and the player standing at (1, 1) able to carry
+/// 50 pounds
+pub fn test_world() -> World {
+    let mut ecs = World::new();
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    register_storages(&mut ecs);
+
+    let mut map = Map::new(1, 10, 10);
+    for y in 1..9 {
+        for x in 1..9 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+            map.visible_tiles[idx] = true;
+        }
+    }
+    ecs.insert(map);
+    ecs.insert(rltk::RandomNumberGenerator::seeded(1));
+    ecs.insert(ItemIdentities::default());
+    ecs.insert(GameLog {
+        entries: Vec::new(),
+    });
+    ecs.insert(RunStats::new(1));
+    ecs.insert(Point::new(1, 1));
+
+    let player = spawner::player(&mut ecs, 1, 1);
+    if let Some(encumbrance) = ecs.write_storage::<Encumbrance>().get_mut(player) {
+        encumbrance.capacity = 50.0;
+    }
+    ecs.insert(player);
+    ecs
+}
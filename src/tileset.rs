@@ -0,0 +1,215 @@
+use super::{wall_mask, FontChoice, Map, TileType};
+use rltk::{FontCharType, Rect, SpriteSheet};
+use std::collections::HashMap;
+
+rltk::embedded_resource!(TERRAIN_SPRITES, "../resources/terrain_tiles.png");
+rltk::embedded_resource!(ENTITY_SPRITES, "../resources/sprites.png");
+
+/// The ASCII console everything is drawn on in ASCII mode, and the UI in both
+pub const ASCII_CONSOLE: usize = 0;
+/// Sprite console for map tiles in tile mode
+pub const TERRAIN_CONSOLE: usize = 1;
+/// Sprite console for entities, drawn over the terrain in tile mode
+pub const ENTITY_CONSOLE: usize = 2;
+/// Sparse console for tooltips and highlights, drawn over everything
+pub const OVERLAY_CONSOLE: usize = 3;
+pub const CONSOLE_COUNT: usize = 4;
+
+const TERRAIN_SHEET_SIZE: i32 = 256;
+const TERRAIN_SPRITE_SIZE: i32 = 16;
+const ENTITY_SHEET_HEIGHT: i32 = 640;
+const ENTITY_SPRITE_SIZE: i32 = 40;
+const ENTITY_SHEET_MARGIN: i32 = 20;
+const ENTITY_SHEET_COLUMNS: i32 = 31;
+const ENTITY_SHEET_ROWS: i32 = 15;
+
+/// Size in pixels of a cell of the sprite consoles. It's the size of the
+/// font's glyphs, so that sprites line up with the ASCII console whichever
+/// font is in use
+#[derive(Copy, Clone)]
+pub struct SpriteCell {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl SpriteCell {
+    pub fn for_font(font: FontChoice) -> SpriteCell {
+        let (_, width, height) = font.file();
+        SpriteCell {
+            width: width as i32,
+            height: height as i32,
+        }
+    }
+
+    /// Where the map cell at screen position `x, y` is on a sprite console
+    pub fn rect(self, x: i32, y: i32) -> Rect {
+        Rect::with_size(x * self.width, y * self.height, self.width, self.height)
+    }
+}
+
+/// How the map and entities are drawn
+#[derive(PartialEq, Copy, Clone)]
+pub enum RenderMode {
+    Ascii,
+    Tiles,
+}
+
+/// Maps terrain and entities to sprites on the terrain and entity sprite
+/// sheets. Entities are matched by their `Renderable` glyph, so anything drawn
+/// with a glyph the tileset knows gets a sprite whatever it's called.
+/// Anything without a sprite is drawn with its ASCII glyph instead
+pub struct TileSet {
+    terrain: HashMap<TileType, usize>,
+    walls: HashMap<u8, usize>,
+    entities: HashMap<FontCharType, usize>,
+    named: HashMap<String, usize>,
+}
+
+impl TileSet {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> TileSet {
+        TileSet {
+            terrain: HashMap::new(),
+            walls: HashMap::new(),
+            entities: HashMap::new(),
+            named: HashMap::new(),
+        }
+    }
+
+    /// Draws every tile of type `tile` with terrain sprite `sprite`
+    pub fn terrain(mut self, tile: TileType, sprite: usize) -> TileSet {
+        self.terrain.insert(tile, sprite);
+        self
+    }
+
+    /// Draws walls whose neighbors match `mask` (see `wall_mask`) with terrain
+    /// sprite `sprite`, instead of the sprite for all walls
+    pub fn wall(mut self, mask: u8, sprite: usize) -> TileSet {
+        self.walls.insert(mask, sprite);
+        self
+    }
+
+    /// Draws entities whose glyph is `glyph` with entity sprite `sprite`
+    pub fn entity(mut self, glyph: char, sprite: usize) -> TileSet {
+        self.entities.insert(rltk::to_cp437(glyph), sprite);
+        self
+    }
+
+    /// Draws entities named `name` with entity sprite `sprite`, instead of
+    /// the sprite for their glyph. Only for telling apart creatures that
+    /// share a glyph; disguised items must be left to their glyph
+    pub fn named<S: ToString>(mut self, name: S, sprite: usize) -> TileSet {
+        self.named.insert(name.to_string(), sprite);
+        self
+    }
+
+    /// Returns the terrain sprite for the tile at `idx`, if it has one
+    pub fn terrain_sprite(&self, map: &Map, idx: usize) -> Option<usize> {
+        let tile = map.tiles[idx];
+        if tile == TileType::Wall {
+            let x = idx as i32 % map.width;
+            let y = idx as i32 / map.width;
+            if let Some(sprite) = wall_mask(map, x, y).and_then(|m| self.walls.get(&m)) {
+                return Some(*sprite);
+            }
+        }
+        self.terrain.get(&tile).copied()
+    }
+
+    /// Returns the entity sprite for an entity drawn as `glyph` and called
+    /// `name`, if it has one
+    pub fn entity_sprite(&self, glyph: FontCharType, name: Option<&str>) -> Option<usize> {
+        name.and_then(|n| self.named.get(n))
+            .or_else(|| self.entities.get(&glyph))
+            .copied()
+    }
+}
+
+/// Index of the entity sprite in column `col` and row `row` of the sheet
+const fn entity_cell(col: i32, row: i32) -> usize {
+    (row * ENTITY_SHEET_COLUMNS + col) as usize
+}
+
+/// Index of the terrain sprite for walls whose neighbors match `mask`. The
+/// second row of the sheet has one for each mask, in order
+const fn wall_cell(mask: u8) -> usize {
+    (TERRAIN_SHEET_SIZE / TERRAIN_SPRITE_SIZE) as usize + mask as usize
+}
+
+pub fn default_tileset() -> TileSet {
+    let mut tileset = TileSet::new()
+        .terrain(TileType::Wall, 0)
+        .terrain(TileType::Floor, 1)
+        .terrain(TileType::DownStairs, 3);
+    for mask in 0..16 {
+        tileset = tileset.wall(mask, wall_cell(mask));
+    }
+
+    tileset
+        .entity('@', entity_cell(4, 9))
+        .entity('g', entity_cell(9, 9))
+        .entity('o', entity_cell(11, 9))
+        .entity('r', entity_cell(3, 10))
+        .entity('$', entity_cell(21, 14))
+        .entity('☼', entity_cell(9, 5))
+        .entity('¡', entity_cell(7, 8))
+        .entity(')', entity_cell(4, 8))
+        .entity('/', entity_cell(20, 0))
+        .entity('(', entity_cell(18, 7))
+        .entity('[', entity_cell(24, 12))
+        .entity('=', entity_cell(24, 7))
+        .entity('"', entity_cell(29, 13))
+        .entity('%', entity_cell(1, 8))
+        .entity('♣', entity_cell(13, 5))
+        .entity('*', entity_cell(30, 10))
+        .entity('-', entity_cell(18, 8))
+        .entity('|', entity_cell(19, 2))
+        .entity('^', entity_cell(6, 3))
+        .named("Shopkeeper", entity_cell(2, 9))
+        .named("Goblin Shaman", entity_cell(12, 2))
+        .named("Dagger", entity_cell(18, 0))
+        .named("Tower Shield", entity_cell(21, 7))
+}
+
+/// Sheet of 16x16 terrain sprites, laid out in rows of 16
+pub fn terrain_sheet() -> SpriteSheet {
+    rltk::link_resource!(TERRAIN_SPRITES, "resources/terrain_tiles.png");
+
+    let per_row = TERRAIN_SHEET_SIZE / TERRAIN_SPRITE_SIZE;
+    let mut sheet = SpriteSheet::new("resources/terrain_tiles.png");
+    for row in 0..per_row {
+        for col in 0..per_row {
+            sheet = sheet.add_sprite(sheet_rect(
+                col * TERRAIN_SPRITE_SIZE,
+                row * TERRAIN_SPRITE_SIZE,
+                TERRAIN_SPRITE_SIZE,
+                TERRAIN_SHEET_SIZE,
+            ));
+        }
+    }
+    sheet
+}
+
+/// Sheet of creature and item sprites on a 40 pixel grid, inside a margin
+pub fn entity_sheet() -> SpriteSheet {
+    rltk::link_resource!(ENTITY_SPRITES, "resources/sprites.png");
+
+    let mut sheet = SpriteSheet::new("resources/sprites.png");
+    for row in 0..ENTITY_SHEET_ROWS {
+        for col in 0..ENTITY_SHEET_COLUMNS {
+            sheet = sheet.add_sprite(sheet_rect(
+                ENTITY_SHEET_MARGIN + col * ENTITY_SPRITE_SIZE,
+                ENTITY_SHEET_MARGIN + row * ENTITY_SPRITE_SIZE,
+                ENTITY_SPRITE_SIZE,
+                ENTITY_SHEET_HEIGHT,
+            ));
+        }
+    }
+    sheet
+}
+
+/// Sprite sheets are loaded upside down, so sprites are located from the
+/// bottom of the image rather than the top
+fn sheet_rect(x: i32, y: i32, size: i32, sheet_height: i32) -> Rect {
+    Rect::with_size(x, sheet_height - y - size, size, size)
+}
@@ -1,6 +1,6 @@
 use super::{
-    gamelog::GameLog, EntityMoved, EntryTrigger, Hidden, InflictsDamage, Map, Name,
-    ParticleBuilder, Position, SingleActivation, SufferDamage,
+    gamelog::GameLog, DamageSource, EntityMoved, EntryTrigger, Hidden, InflictsDamage, Map, Name,
+    NoiseBuilder, ParticleBuilder, Position, RunStats, SingleActivation, SufferDamage,
 };
 use specs::prelude::*;
 
@@ -21,6 +21,9 @@ impl<'a> System<'a> for TriggerSystem {
         WriteExpect<'a, ParticleBuilder>,
         WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, SingleActivation>,
+        WriteExpect<'a, NoiseBuilder>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -37,6 +40,9 @@ impl<'a> System<'a> for TriggerSystem {
             mut particle_builder,
             mut inflict_damage,
             single_activation,
+            mut noise,
+            player_entity,
+            mut run_stats,
         ) = data;
 
         // Save all entities that will be removed
@@ -56,8 +62,17 @@ impl<'a> System<'a> for TriggerSystem {
                             let name = names.get(*entity_id);
                             if let Some(name) = name {
                                 log.entries.push(format!("{} triggers!", &name.name));
+                                if entity == *player_entity {
+                                    *run_stats
+                                        .traps_triggered
+                                        .entry(name.name.clone())
+                                        .or_insert(0) += 1;
+                                }
                             }
 
+                            // Traps snapping shut can be heard a long way off
+                            noise.request(pos.x, pos.y, 10);
+
                             // If the trap is damage inflicting, do it
                             let damage = inflicts_damage.get(*entity_id);
                             if let Some(damage) = damage {
@@ -69,10 +84,15 @@ impl<'a> System<'a> for TriggerSystem {
                                     rltk::to_cp437('‼'),
                                     200.0,
                                 );
+                                let trap_name = match names.get(*entity_id) {
+                                    Some(name) => format!("a {}", name.name),
+                                    None => "a trap".to_string(),
+                                };
                                 SufferDamage::new_damage(
                                     &mut inflict_damage,
                                     entity,
                                     damage.damage,
+                                    DamageSource::new(trap_name, false),
                                 );
                             }
 
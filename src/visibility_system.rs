@@ -1,10 +1,14 @@
-use crate::{gamelog::GameLog, Hidden, Map, Name, Player, Position, RevealChance, Viewshed};
+use crate::{
+    gamelog::GameLog, Awareness, Hidden, Map, Name, Player, Position, RevealChance, RunStats,
+    Viewshed,
+};
 use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
 pub struct VisibilitySystem {}
 
 impl<'a> System<'a> for VisibilitySystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteExpect<'a, Map>,
         Entities<'a>,
@@ -16,6 +20,8 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, RevealChance>,
+        ReadStorage<'a, Awareness>,
+        WriteExpect<'a, RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,45 +36,70 @@ impl<'a> System<'a> for VisibilitySystem {
             mut log,
             names,
             reveal_chances,
+            awareness,
+            mut run_stats,
         ) = data;
 
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
-            if viewshed.dirty {
+            let is_player = player.get(ent).is_some();
+
+            // What the player can see also depends on the light, which changes
+            // as light sources move, so their view is always refreshed
+            if viewshed.dirty || is_player {
+                let moved = viewshed.dirty;
                 viewshed.dirty = false;
                 viewshed.visible_tiles.clear();
-                viewshed.visible_tiles =
-                    field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+                let origin = Point::new(pos.x, pos.y);
+                viewshed.visible_tiles = field_of_view(origin, viewshed.range, &*map);
                 viewshed
                     .visible_tiles
                     .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
 
                 // If this is the player, reveal what they can see
-                let _p: Option<&Player> = player.get(ent);
-                if let Some(_p) = _p {
+                if is_player {
+                    // Only lit tiles can be seen, except those close enough to
+                    // feel your way around
+                    let lit_map = &*map;
+                    viewshed.visible_tiles.retain(|p| {
+                        lit_map.is_lit(lit_map.xy_idx(p.x, p.y))
+                            || rltk::DistanceAlg::Pythagoras.distance2d(origin, *p) < 1.5
+                    });
+
+                    // Perceptive players spot hidden things more often
+                    let perception = match awareness.get(ent) {
+                        Some(aware) => i32::max(1, aware.perception),
+                        None => 1,
+                    };
                     for t in map.visible_tiles.iter_mut() {
                         *t = false
                     }
                     for vis in viewshed.visible_tiles.iter() {
-                        for vis in viewshed.visible_tiles.iter() {
-                            let idx = map.xy_idx(vis.x, vis.y);
-                            map.revealed_tiles[idx] = true;
-                            map.visible_tiles[idx] = true;
+                        let idx = map.xy_idx(vis.x, vis.y);
+                        map.revealed_tiles[idx] = true;
+                        map.visible_tiles[idx] = true;
+
+                        // Only roll to spot things when the view has changed
+                        if !moved {
+                            continue;
+                        }
 
-                            // Try to reveal things that have a chance
-                            for e in map.tile_content[idx].iter() {
-                                let maybe_hidden = hidden.get(*e);
-                                let maybe_reveal_chance = reveal_chances.get(*e);
-                                if let (Some(_), Some(reveal_chance)) =
-                                    (maybe_hidden, maybe_reveal_chance)
-                                {
-                                    if rng.roll_dice(1, reveal_chance.chance) == 1 {
-                                        let name = names.get(*e);
-                                        if let Some(name) = name {
-                                            log.entries
-                                                .push(format!("You spotted a {}.", &name.name));
-                                        }
-                                        hidden.remove(*e);
+                        // Try to reveal things that have a chance
+                        for e in map.tile_content[idx].iter() {
+                            let maybe_hidden = hidden.get(*e);
+                            let maybe_reveal_chance = reveal_chances.get(*e);
+                            if let (Some(_), Some(reveal_chance)) =
+                                (maybe_hidden, maybe_reveal_chance)
+                            {
+                                if rng.roll_dice(1, reveal_chance.chance) <= perception {
+                                    let name = names.get(*e);
+                                    if let Some(name) = name {
+                                        log.entries.push(format!("You spotted a {}.", &name.name));
+                                        *run_stats
+                                            .traps_spotted
+                                            .entry(name.name.clone())
+                                            .or_insert(0) += 1;
                                     }
+                                    hidden.remove(*e);
                                 }
                             }
                         }